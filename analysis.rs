@@ -0,0 +1,92 @@
+//! Axis VM bytecode analysis
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use ethereum_types::H256;
+
+/// Bitmap of the code offsets holding a JUMPDEST instruction. Bytes inside
+/// PUSH immediates are data and never valid destinations, even if they
+/// happen to equal 0x5b.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JumpTable {
+    bits: Vec<u8>,
+}
+
+impl JumpTable {
+    /// Walk `code` once and mark every reachable JUMPDEST.
+    pub fn analyze(code: &[u8]) -> JumpTable {
+        let mut bits = vec![0u8; (code.len() + 7) / 8];
+        let mut pc = 0;
+
+        while pc < code.len() {
+            let opcode = code[pc];
+            if opcode == 0x5b {
+                bits[pc / 8] |= 1 << (pc % 8);
+            } else if (0x60..=0x7f).contains(&opcode) {
+                // Skip the PUSH1..PUSH32 immediate
+                pc += (opcode - 0x5f) as usize;
+            }
+            pc += 1;
+        }
+
+        JumpTable { bits }
+    }
+
+    /// Returns true if `pc` is a valid jump destination.
+    #[inline]
+    pub fn is_valid(&self, pc: usize) -> bool {
+        match self.bits.get(pc / 8) {
+            Some(byte) => byte & (1 << (pc % 8)) != 0,
+            None => false,
+        }
+    }
+}
+
+/// Number of jump tables `jump_table` keeps; beyond it the least recently
+/// used one is dropped
+const CACHE_CAPACITY: usize = 1024;
+
+/// Jump tables of deployed code, with the tick of their last use
+#[derive(Default)]
+struct JumpTableCache {
+    tables: HashMap<H256, (Rc<JumpTable>, u64)>,
+    tick: u64,
+}
+
+impl JumpTableCache {
+    fn get_or_analyze(&mut self, code_hash: H256, code: &[u8]) -> Rc<JumpTable> {
+        self.tick += 1;
+        if let Some(entry) = self.tables.get_mut(&code_hash) {
+            entry.1 = self.tick;
+            return entry.0.clone();
+        }
+
+        if self.tables.len() >= CACHE_CAPACITY {
+            let oldest = self
+                .tables
+                .iter()
+                .min_by_key(|&(_, &(_, used))| used)
+                .map(|(hash, _)| *hash);
+            if let Some(oldest) = oldest {
+                self.tables.remove(&oldest);
+            }
+        }
+        let table = Rc::new(JumpTable::analyze(code));
+        self.tables.insert(code_hash, (table.clone(), self.tick));
+        table
+    }
+}
+
+thread_local! {
+    static JUMP_TABLES: RefCell<JumpTableCache> = RefCell::new(JumpTableCache::default());
+}
+
+/// Jump table for the deployed `code` whose hash, as stored in its account,
+/// is `code_hash`. Tables are analysed once and cached for later frames
+/// running the same code. Init code is never cached: analyse it with
+/// `JumpTable::analyze`.
+pub fn jump_table(code_hash: H256, code: &[u8]) -> Rc<JumpTable> {
+    JUMP_TABLES.with(|tables| tables.borrow_mut().get_or_analyze(code_hash, code))
+}
@@ -0,0 +1,171 @@
+//! Axis VM word arithmetic
+//!
+//! Stack words are unsigned 256-bit integers. Signed opcodes interpret them
+//! as two's-complement values, and every operation wraps modulo 2^256 the
+//! way the EVM does.
+
+use ethereum_types::{U256, U512};
+
+/// Returns true if the two's-complement sign bit is set.
+#[inline]
+pub fn is_negative(value: U256) -> bool {
+    value.bit(255)
+}
+
+/// Two's-complement negation, wrapping `-2^255` onto itself.
+#[inline]
+pub fn negate(value: U256) -> U256 {
+    (!value).overflowing_add(U256::one()).0
+}
+
+/// Split a word into its sign and absolute value.
+#[inline]
+fn sign_abs(value: U256) -> (bool, U256) {
+    if is_negative(value) {
+        (true, negate(value))
+    } else {
+        (false, value)
+    }
+}
+
+/// Truncate a 512-bit intermediate to its low 256 bits.
+#[inline]
+fn low_u256(value: U512) -> U256 {
+    let U512(ref words) = value;
+    U256([words[0], words[1], words[2], words[3]])
+}
+
+/// Unsigned division, yielding zero when dividing by zero.
+pub fn div(a: U256, b: U256) -> U256 {
+    a.checked_div(b).unwrap_or_else(U256::zero)
+}
+
+/// Unsigned modulo, yielding zero when the modulus is zero.
+pub fn rem(a: U256, b: U256) -> U256 {
+    a.checked_rem(b).unwrap_or_else(U256::zero)
+}
+
+/// Signed division rounding towards zero. `-2^255 / -1` overflows back to
+/// `-2^255`, and division by zero yields zero.
+pub fn sdiv(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::zero();
+    }
+
+    let (a_neg, a_abs) = sign_abs(a);
+    let (b_neg, b_abs) = sign_abs(b);
+    let quotient = a_abs / b_abs;
+
+    if a_neg != b_neg {
+        negate(quotient)
+    } else {
+        quotient
+    }
+}
+
+/// Signed modulo. The result takes the sign of the dividend, and a zero
+/// modulus yields zero.
+pub fn smod(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::zero();
+    }
+
+    let (a_neg, a_abs) = sign_abs(a);
+    let (_, b_abs) = sign_abs(b);
+    let remainder = a_abs % b_abs;
+
+    if a_neg {
+        negate(remainder)
+    } else {
+        remainder
+    }
+}
+
+/// `(a + b) % n` computed without intermediate overflow.
+pub fn addmod(a: U256, b: U256, n: U256) -> U256 {
+    if n.is_zero() {
+        return U256::zero();
+    }
+
+    let sum = U512::from(a) + U512::from(b);
+    low_u256(sum % U512::from(n))
+}
+
+/// `(a * b) % n` computed without intermediate overflow.
+pub fn mulmod(a: U256, b: U256, n: U256) -> U256 {
+    if n.is_zero() {
+        return U256::zero();
+    }
+
+    low_u256(a.full_mul(b) % U512::from(n))
+}
+
+/// Extend the sign of the `(byte + 1)`-byte two's-complement value in `value`.
+pub fn signextend(byte: U256, value: U256) -> U256 {
+    if byte >= U256::from(31) {
+        return value;
+    }
+
+    let bit = byte.low_u32() as usize * 8 + 7;
+    let mask = (U256::one() << bit) - U256::one();
+
+    if value.bit(bit) {
+        value | !mask
+    } else {
+        value & mask
+    }
+}
+
+/// Signed less-than comparison.
+pub fn slt(a: U256, b: U256) -> bool {
+    match (is_negative(a), is_negative(b)) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => a < b,
+    }
+}
+
+/// Logical left shift; shifts of 256 or more yield zero.
+pub fn shl(shift: U256, value: U256) -> U256 {
+    if shift >= U256::from(256) {
+        U256::zero()
+    } else {
+        value << shift.low_u32() as usize
+    }
+}
+
+/// Logical right shift; shifts of 256 or more yield zero.
+pub fn shr(shift: U256, value: U256) -> U256 {
+    if shift >= U256::from(256) {
+        U256::zero()
+    } else {
+        value >> shift.low_u32() as usize
+    }
+}
+
+/// Arithmetic right shift, filling the vacated bits with the sign bit.
+pub fn sar(shift: U256, value: U256) -> U256 {
+    let negative = is_negative(value);
+
+    if shift >= U256::from(256) {
+        return if negative { U256::max_value() } else { U256::zero() };
+    }
+
+    let shift = shift.low_u32() as usize;
+    let shifted = value >> shift;
+    if negative && shift > 0 {
+        shifted | !(U256::max_value() >> shift)
+    } else {
+        shifted
+    }
+}
+
+/// The `index`-th byte of `value`, counting from the most significant byte.
+/// Indices of 32 or more yield zero.
+pub fn byte(index: U256, value: U256) -> U256 {
+    if index >= U256::from(32) {
+        U256::zero()
+    } else {
+        U256::from(value.byte(31 - index.low_u32() as usize))
+    }
+}
@@ -0,0 +1,513 @@
+//! Axis VM assembler
+//!
+//! Turns assembly text into bytecode. One instruction per line, using the
+//! mnemonics of `opcode`; `;` and `//` start comments. On top of plain
+//! instructions the assembler understands:
+//!
+//! * `name:` defines a label and emits a JUMPDEST at its offset;
+//!   `PUSH name` pushes that offset.
+//! * `PUSH value` without a size picks the smallest PUSH1..PUSH32 that
+//!   holds the value or label offset. `PUSHn value` keeps the given size.
+//! * `%macro name a b` ... `%end` defines a macro; `name x y` expands its
+//!   body with `$a` and `$b` replaced.
+//! * `.data name 0x..` places bytes after the code; `PUSH name` pushes
+//!   their offset and `PUSH size(name)` their length.
+//! * `.bytes 0x..` emits raw bytes in place.
+//!
+//! The listing of `disasm::to_text` assembles back to the same bytecode:
+//! offset prefixes like `0x001f:` are skipped, and `INVALID(0x..)`,
+//! truncated PUSHes and `METADATA` are emitted as the original bytes.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use ethereum_types::U256;
+use super::opcode;
+
+/// Why assembly failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    UnknownMnemonic(String),
+    /// A malformed number, hex string or label
+    InvalidOperand(String),
+    MissingOperand,
+    /// The operand does not fit in the PUSH size
+    ImmediateTooLarge,
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    /// `%macro` without `%end`
+    UnterminatedMacro(String),
+    /// A macro used with the wrong number of arguments
+    MacroArity(String),
+    /// A macro that expands to itself
+    RecursiveMacro(String),
+}
+
+/// Error at a line of the source, numbered from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            AsmErrorKind::UnknownMnemonic(ref name) => write!(f, "unknown mnemonic `{}`", name),
+            AsmErrorKind::InvalidOperand(ref operand) => write!(f, "invalid operand `{}`", operand),
+            AsmErrorKind::MissingOperand => write!(f, "missing operand"),
+            AsmErrorKind::ImmediateTooLarge => write!(f, "operand too large for the PUSH"),
+            AsmErrorKind::UndefinedLabel(ref name) => write!(f, "undefined label `{}`", name),
+            AsmErrorKind::DuplicateLabel(ref name) => write!(f, "label `{}` already defined", name),
+            AsmErrorKind::UnterminatedMacro(ref name) => write!(f, "macro `{}` has no %end", name),
+            AsmErrorKind::MacroArity(ref name) => {
+                write!(f, "wrong number of arguments to macro `{}`", name)
+            }
+            AsmErrorKind::RecursiveMacro(ref name) => {
+                write!(f, "macro `{}` expands to itself", name)
+            }
+        }
+    }
+}
+
+fn error(line: usize, kind: AsmErrorKind) -> AsmError {
+    AsmError { line, kind }
+}
+
+/// Value pushed by a PUSH
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Value(U256),
+    /// Offset of a label or data section
+    Label(String),
+    /// Length of a data section
+    Size(String),
+}
+
+/// A piece of assembled code before label resolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    /// Bytes known up front: opcodes, sized PUSHes, raw bytes
+    Bytes(Vec<u8>),
+    /// A label definition, emitted as JUMPDEST
+    Label(String),
+    /// A PUSH of the smallest size holding the operand
+    Push(Operand),
+    /// A PUSH of fixed size whose operand is resolved later
+    SizedPush(usize, Operand),
+}
+
+/// Bytes placed after the code
+struct DataSection {
+    line: usize,
+    name: String,
+    bytes: Vec<u8>,
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<String>,
+}
+
+/// Deepest macro nesting before an expansion is considered recursive
+const MAX_MACRO_DEPTH: usize = 64;
+
+/// Assemble `source` into bytecode.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let lines = expand_macros(source)?;
+
+    let mut items = Vec::new();
+    let mut data = Vec::new();
+    let mut item_lines = Vec::new();
+    for (line_no, line) in lines {
+        let before = items.len();
+        parse_line(line_no, &line, &mut items, &mut data)?;
+        item_lines.extend((before..items.len()).map(|_| line_no));
+    }
+
+    // PUSH sizes depend on label offsets and the offsets on the sizes.
+    // Sizes only ever grow, so this settles after a few passes.
+    let mut sizes: Vec<usize> = items.iter().map(|_| 1).collect();
+    let mut labels;
+    loop {
+        labels = layout(&items, &sizes, &data, &item_lines)?;
+        let mut changed = false;
+        for (i, item) in items.iter().enumerate() {
+            if let Item::Push(ref operand) = *item {
+                let value = resolve(operand, &labels, &data, item_lines[i])?;
+                let needed = byte_len(value).max(1);
+                if needed > sizes[i] {
+                    sizes[i] = needed;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut code = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        match *item {
+            Item::Bytes(ref bytes) => code.extend_from_slice(bytes),
+            Item::Label(_) => code.push(0x5b),
+            Item::Push(ref operand) => {
+                let value = resolve(operand, &labels, &data, item_lines[i])?;
+                push_bytes(&mut code, sizes[i], value);
+            }
+            Item::SizedPush(size, ref operand) => {
+                let value = resolve(operand, &labels, &data, item_lines[i])?;
+                if byte_len(value) > size {
+                    return Err(error(item_lines[i], AsmErrorKind::ImmediateTooLarge));
+                }
+                push_bytes(&mut code, size, value);
+            }
+        }
+    }
+    for section in &data {
+        code.extend_from_slice(&section.bytes);
+    }
+    Ok(code)
+}
+
+/// Offsets of every label and data section, given the PUSH sizes
+fn layout(
+    items: &[Item],
+    sizes: &[usize],
+    data: &[DataSection],
+    item_lines: &[usize],
+) -> Result<HashMap<String, usize>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut offset = 0;
+    for (i, item) in items.iter().enumerate() {
+        offset += match *item {
+            Item::Bytes(ref bytes) => bytes.len(),
+            Item::Label(ref name) => {
+                if labels.insert(name.clone(), offset).is_some() {
+                    return Err(error(item_lines[i], AsmErrorKind::DuplicateLabel(name.clone())));
+                }
+                1
+            }
+            Item::Push(_) => 1 + sizes[i],
+            Item::SizedPush(size, _) => 1 + size,
+        };
+    }
+    for section in data {
+        if labels.insert(section.name.clone(), offset).is_some() {
+            let name = section.name.clone();
+            return Err(error(section.line, AsmErrorKind::DuplicateLabel(name)));
+        }
+        offset += section.bytes.len();
+    }
+    Ok(labels)
+}
+
+fn resolve(
+    operand: &Operand,
+    labels: &HashMap<String, usize>,
+    data: &[DataSection],
+    line: usize,
+) -> Result<U256, AsmError> {
+    match *operand {
+        Operand::Value(value) => Ok(value),
+        Operand::Label(ref name) => labels
+            .get(name)
+            .map(|&offset| U256::from(offset))
+            .ok_or_else(|| error(line, AsmErrorKind::UndefinedLabel(name.clone()))),
+        Operand::Size(ref name) => data
+            .iter()
+            .find(|section| section.name == *name)
+            .map(|section| U256::from(section.bytes.len()))
+            .ok_or_else(|| error(line, AsmErrorKind::UndefinedLabel(name.clone()))),
+    }
+}
+
+/// Minimal number of bytes holding `value`
+fn byte_len(value: U256) -> usize {
+    (value.bits() + 7) / 8
+}
+
+/// Emit `PUSH<size> value`
+fn push_bytes(code: &mut Vec<u8>, size: usize, value: U256) {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    code.push(0x5f + size as u8);
+    code.extend_from_slice(&word[32 - size..]);
+}
+
+/// Strip comments, collect macro definitions and expand their uses.
+fn expand_macros(source: &str) -> Result<Vec<(usize, String)>, AsmError> {
+    let mut macros = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String, Macro)> = None;
+
+    for (i, raw) in source.lines().enumerate() {
+        let line_no = i + 1;
+        let line = strip_comment(raw).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let first = tokens.next().expect("non-empty line");
+        if first == "%macro" {
+            let name = tokens
+                .next()
+                .ok_or_else(|| error(line_no, AsmErrorKind::MissingOperand))?;
+            let params = tokens.map(str::to_string).collect();
+            let definition = Macro { params, body: Vec::new() };
+            current = Some((line_no, name.to_string(), definition));
+        } else if first == "%end" {
+            if let Some((_, name, definition)) = current.take() {
+                macros.insert(name, definition);
+            }
+        } else if let Some((_, _, ref mut definition)) = current {
+            definition.body.push(line.to_string());
+        } else {
+            lines.push((line_no, line.to_string()));
+        }
+    }
+    if let Some((line_no, name, _)) = current {
+        return Err(error(line_no, AsmErrorKind::UnterminatedMacro(name)));
+    }
+
+    let mut expanded = Vec::new();
+    for (line_no, line) in lines {
+        expand_line(line_no, &line, &macros, 0, &mut expanded)?;
+    }
+    Ok(expanded)
+}
+
+/// Append `line`, or the expansion of the macro it uses, to `out`. Lines
+/// from a macro body are reported at the line that used the macro.
+fn expand_line(
+    line_no: usize,
+    line: &str,
+    macros: &HashMap<String, Macro>,
+    depth: usize,
+    out: &mut Vec<(usize, String)>,
+) -> Result<(), AsmError> {
+    let mut tokens = line.split_whitespace();
+    let name = tokens.next().expect("non-empty line");
+    let definition = match macros.get(name) {
+        Some(definition) => definition,
+        None => {
+            out.push((line_no, line.to_string()));
+            return Ok(());
+        }
+    };
+    if depth >= MAX_MACRO_DEPTH {
+        return Err(error(line_no, AsmErrorKind::RecursiveMacro(name.to_string())));
+    }
+
+    let args: Vec<&str> = tokens.collect();
+    if args.len() != definition.params.len() {
+        return Err(error(line_no, AsmErrorKind::MacroArity(name.to_string())));
+    }
+    for body_line in &definition.body {
+        let mut body_line = body_line.clone();
+        // Longest names first, so `$ab` is not replaced as `$a` + `b`
+        let mut params: Vec<(&String, &str)> =
+            definition.params.iter().zip(args.iter().cloned()).collect();
+        params.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        for (param, arg) in params {
+            body_line = body_line.replace(&format!("${}", param), arg);
+        }
+        expand_line(line_no, &body_line, macros, depth + 1, out)?;
+    }
+    Ok(())
+}
+
+fn strip_comment(line: &str) -> &str {
+    let end = [line.find(';'), line.find("//")]
+        .iter()
+        .filter_map(|&position| position)
+        .min()
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+/// Returns true if `token` can name a label, macro or data section
+fn is_identifier(token: &str) -> bool {
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse one line of expanded source into `items`, or a data section.
+fn parse_line(
+    line_no: usize,
+    line: &str,
+    items: &mut Vec<Item>,
+    data: &mut Vec<DataSection>,
+) -> Result<(), AsmError> {
+    let mut rest = line.trim();
+
+    // Offset prefix of a disassembly listing, e.g. `0x001f:`
+    if let Some(colon) = rest.find(':') {
+        let head = &rest[..colon];
+        if head.starts_with(|c: char| c.is_ascii_digit()) && !head.contains(char::is_whitespace) {
+            rest = rest[colon + 1..].trim();
+        } else if is_identifier(head) {
+            items.push(Item::Label(head.to_string()));
+            rest = rest[colon + 1..].trim();
+        }
+    }
+    if rest.is_empty() {
+        return Ok(());
+    }
+
+    let mut tokens = rest.split_whitespace();
+    let mnemonic = tokens.next().expect("non-empty line");
+    let operand = tokens.next();
+    let invalid = |token: &str| error(line_no, AsmErrorKind::InvalidOperand(token.to_string()));
+    let required = || operand.ok_or_else(|| error(line_no, AsmErrorKind::MissingOperand));
+
+    match mnemonic {
+        ".bytes" | "METADATA" => {
+            let hex = required()?;
+            items.push(Item::Bytes(parse_hex(hex).ok_or_else(|| invalid(hex))?));
+        }
+        ".data" => {
+            let name = required()?;
+            if !is_identifier(name) {
+                return Err(invalid(name));
+            }
+            if data.iter().any(|section| section.name == name) {
+                return Err(error(line_no, AsmErrorKind::DuplicateLabel(name.to_string())));
+            }
+            let hex = tokens
+                .next()
+                .ok_or_else(|| error(line_no, AsmErrorKind::MissingOperand))?;
+            let bytes = parse_hex(hex).ok_or_else(|| invalid(hex))?;
+            data.push(DataSection { line: line_no, name: name.to_string(), bytes });
+        }
+        _ if mnemonic.to_ascii_uppercase() == "PUSH" => {
+            items.push(Item::Push(parse_operand(line_no, required()?)?));
+        }
+        _ if mnemonic.to_ascii_uppercase().starts_with("INVALID(") => {
+            let bytes = parse_hex(mnemonic[8..].trim_end_matches(')'))
+                .filter(|bytes| bytes.len() == 1);
+            items.push(Item::Bytes(bytes.ok_or_else(|| invalid(mnemonic))?));
+        }
+        _ => {
+            let unknown = AsmErrorKind::UnknownMnemonic(mnemonic.to_string());
+            let opcode = opcode::from_mnemonic(mnemonic).ok_or_else(|| error(line_no, unknown))?;
+            let size = opcode::immediate_len(opcode);
+            if size == 0 {
+                items.push(Item::Bytes(vec![opcode]));
+                return Ok(());
+            }
+
+            let operand = required()?;
+            // A PUSH cut off by the end of the code keeps its short immediate
+            if tokens.next() == Some("(truncated)") {
+                let mut bytes = vec![opcode];
+                bytes.extend(parse_hex(operand).ok_or_else(|| invalid(operand))?);
+                items.push(Item::Bytes(bytes));
+                return Ok(());
+            }
+            // Hex immediates keep their leading zeros
+            if let Some(bytes) = parse_hex(operand) {
+                if bytes.len() > size {
+                    return Err(error(line_no, AsmErrorKind::ImmediateTooLarge));
+                }
+                let mut encoded = vec![opcode];
+                encoded.resize(1 + size - bytes.len(), 0);
+                encoded.extend(bytes);
+                items.push(Item::Bytes(encoded));
+                return Ok(());
+            }
+            items.push(Item::SizedPush(size, parse_operand(line_no, operand)?));
+        }
+    }
+    Ok(())
+}
+
+/// A PUSH operand: a decimal or hex number, a label, or `size(data)`
+fn parse_operand(line_no: usize, token: &str) -> Result<Operand, AsmError> {
+    if token.starts_with("size(") && token.ends_with(')') {
+        return Ok(Operand::Size(token[5..token.len() - 1].to_string()));
+    }
+    if is_identifier(token) {
+        return Ok(Operand::Label(token.to_string()));
+    }
+    if let Some(bytes) = parse_hex(token) {
+        if bytes.len() <= 32 {
+            return Ok(Operand::Value(U256::from(&bytes[..])));
+        }
+    } else if let Ok(value) = U256::from_dec_str(token) {
+        return Ok(Operand::Value(value));
+    }
+    Err(error(line_no, AsmErrorKind::InvalidOperand(token.to_string())))
+}
+
+/// Bytes of a `0x`-prefixed hex string; an odd digit count is padded with
+/// a leading zero.
+fn parse_hex(token: &str) -> Option<Vec<u8>> {
+    if !token.starts_with("0x") && !token.starts_with("0X") {
+        return None;
+    }
+    let digits = &token[2..];
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits = if digits.len() % 2 == 1 {
+        format!("0{}", digits)
+    } else {
+        digits.to_string()
+    };
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::disasm;
+
+    const PROGRAM: &str = "
+        %macro store offset value
+            PUSH $value
+            PUSH $offset
+            MSTORE
+        %end
+
+            PUSH size(greeting)   ; copy the data section to memory
+            PUSH greeting
+            PUSH 0
+            CODECOPY
+            store 0x20 0x2a
+            PUSH done
+            JUMP
+        unreachable:
+            INVALID
+        done:
+            PUSH 0x40
+            PUSH 0
+            RETURN
+
+        .data greeting 0x0c2a
+        .data meta 0xa16161010004
+    ";
+
+    #[test]
+    fn disassembly_round_trip() {
+        let code = assemble(PROGRAM).unwrap();
+        let instructions = disasm::disassemble(&code);
+        match instructions.last() {
+            Some(&disasm::Instruction::Metadata { .. }) => {}
+            other => panic!("expected a metadata trailer, got {:?}", other),
+        }
+
+        let listing = disasm::to_text(&instructions);
+        assert_eq!(assemble(&listing), Ok(code));
+    }
+}
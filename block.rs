@@ -0,0 +1,69 @@
+//! Axis block context
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use ethereum_types::{H160, H256, U256};
+
+/// Source of the hashes of recent blocks for BLOCKHASH. Only the 256 most
+/// recent blocks are ever asked for.
+pub trait BlockHashProvider {
+    /// Hash of block `number`, or zero if it is unknown.
+    fn block_hash(&self, number: u64) -> H256;
+}
+
+/// Provider that knows no block hashes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoBlockHashes;
+
+impl BlockHashProvider for NoBlockHashes {
+    fn block_hash(&self, _number: u64) -> H256 {
+        H256::zero()
+    }
+}
+
+impl BlockHashProvider for HashMap<u64, H256> {
+    fn block_hash(&self, number: u64) -> H256 {
+        self.get(&number).cloned().unwrap_or_default()
+    }
+}
+
+/// The block a transaction executes in
+#[derive(Clone)]
+pub struct BlockEnv {
+    pub number: u64,
+    pub coinbase: H160,
+    pub timestamp: u64,
+    pub difficulty: U256,
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    /// Price of blob gas (EIP-7516)
+    pub blob_base_fee: U256,
+    pub block_hashes: Rc<dyn BlockHashProvider>,
+}
+
+impl Default for BlockEnv {
+    fn default() -> BlockEnv {
+        BlockEnv {
+            number: 0,
+            coinbase: H160::zero(),
+            timestamp: 0,
+            difficulty: U256::zero(),
+            gas_limit: 0,
+            base_fee: U256::zero(),
+            blob_base_fee: U256::zero(),
+            block_hashes: Rc::new(NoBlockHashes),
+        }
+    }
+}
+
+impl BlockEnv {
+    /// Hash of block `number` as seen by BLOCKHASH: zero unless it is one
+    /// of the 256 blocks before the current one.
+    pub fn block_hash(&self, number: U256) -> H256 {
+        if number >= U256::from(self.number) || U256::from(self.number) - number > U256::from(256) {
+            return H256::zero();
+        }
+        self.block_hashes.block_hash(number.low_u64())
+    }
+}
@@ -0,0 +1,550 @@
+//! Axis VM interactive debugger
+//!
+//! An `Inspector` that stops the interpreter before instructions and reads
+//! commands from a terminal: single-step, run to a breakpoint on a pc or an
+//! opcode, watch storage slots, and look at the stack, memory, storage,
+//! return data and call frames. Every step is recorded, so earlier steps
+//! can be looked at again with `back`. Going back rewinds the pc, stack,
+//! memory and return data only; storage always shows the live state.
+//!
+//! Run a transaction under the debugger with
+//! `vm.inspect(&mut state, &mut Debugger::new(stdin.lock(), stdout()))`.
+
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+use ethereum_types::{H160, U256};
+use super::inspector::{CallInputs, CreateInputs, FrameResult, Inspector};
+use super::opcode;
+use super::vm::{Halt, AXISVM};
+use super::world::WorldState;
+
+/// Steps kept for `back` by default
+const DEFAULT_HISTORY: usize = 1024;
+
+/// Bytes of stack, memory and return data kept for `back`; older steps are
+/// dropped first once the recorded frames grow past this
+const MAX_HISTORY_BYTES: usize = 64 << 20;
+
+const HELP: &str = "\
+step, s                 run the next instruction
+continue, c             run to the next breakpoint or watchpoint
+break, b <pc|OPCODE>    stop before the instruction at pc, or before every OPCODE
+delete <pc|OPCODE>      remove a breakpoint
+watch, w <key> [addr]   stop after a write changes a storage slot of the
+                        current (or given) account
+unwatch <key> [addr]    remove a watchpoint
+info, i                 show pc, opcode, gas and depth
+stack                   show the stack, top first
+memory, m [off] [len]   hexdump memory
+storage <key>           show a storage slot of the current account
+returndata, r           show the output of the last sub-call
+backtrace, bt           show the call frames
+back [n]                look at the pc, stack and memory n steps earlier;
+                        storage is not rewound
+forward [n]             look at the state n steps later
+detach                  run to the end without stopping
+help, h                 show this help
+";
+
+/// When the debugger stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Before the next instruction
+    Step,
+    /// At breakpoints and watchpoints
+    Continue,
+    /// Never
+    Detached,
+}
+
+/// A breakpoint on a code offset or on an opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Breakpoint {
+    Pc(usize),
+    Opcode(u8),
+}
+
+/// A storage slot to watch, with the value it had when last checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Watchpoint {
+    address: H160,
+    key: U256,
+    value: U256,
+}
+
+/// Frame state before a recorded instruction. Memory and return data are
+/// shared with the step before when its instruction cannot have changed
+/// them.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    pc: usize,
+    opcode: u8,
+    gas: usize,
+    depth: usize,
+    address: H160,
+    stack: Vec<U256>,
+    memory: Rc<Vec<u8>>,
+    return_data: Rc<Vec<u8>>,
+}
+
+impl Snapshot {
+    /// Returns true if `self` was recorded right before `next` in the same
+    /// frame.
+    fn precedes_in_frame(&self, next: &Snapshot) -> bool {
+        self.depth == next.depth && self.address == next.address
+    }
+}
+
+/// Interactive debugger reading commands from `input` and writing to
+/// `output`
+pub struct Debugger<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    mode: Mode,
+    breakpoints: HashSet<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    /// A watchpoint fired; stop before the next instruction
+    watch_hit: bool,
+    history: VecDeque<Snapshot>,
+    history_limit: usize,
+    /// Bytes held by `history`, counting shared memory and return data once
+    history_bytes: usize,
+    /// Index in `history` of the step being looked at
+    cursor: usize,
+    /// Code addresses of the running frames, outermost first
+    frames: Vec<H160>,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    /// Debugger that stops before the first instruction
+    pub fn new(input: R, output: W) -> Debugger<R, W> {
+        Debugger {
+            input,
+            output,
+            mode: Mode::Step,
+            breakpoints: HashSet::new(),
+            watchpoints: Vec::new(),
+            watch_hit: false,
+            history: VecDeque::new(),
+            history_limit: DEFAULT_HISTORY,
+            history_bytes: 0,
+            cursor: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Keep the last `limit` steps for `back` instead of the default 1024.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit.max(1);
+        self.trim_history();
+    }
+
+    /// Drop the oldest steps until the history fits its step and byte
+    /// limits. The latest step is always kept.
+    fn trim_history(&mut self) {
+        while self.history.len() > self.history_limit
+            || (self.history_bytes > MAX_HISTORY_BYTES && self.history.len() > 1)
+        {
+            let oldest = self.history.pop_front().expect("non-empty history");
+            self.history_bytes -= 32 * oldest.stack.len();
+            // Data still shared with a later step stays in the history
+            if Rc::strong_count(&oldest.memory) == 1 {
+                self.history_bytes -= oldest.memory.len();
+            }
+            if Rc::strong_count(&oldest.return_data) == 1 {
+                self.history_bytes -= oldest.return_data.len();
+            }
+        }
+        self.cursor = self.cursor.min(self.history.len().saturating_sub(1));
+    }
+
+    /// Stop before the instruction at `pc` in any frame.
+    pub fn break_at_pc(&mut self, pc: usize) {
+        self.breakpoints.insert(Breakpoint::Pc(pc));
+    }
+
+    /// Stop before every `opcode`.
+    pub fn break_at_opcode(&mut self, opcode: u8) {
+        self.breakpoints.insert(Breakpoint::Opcode(opcode));
+    }
+
+    /// Start without stopping until a breakpoint or watchpoint is hit.
+    pub fn run_to_breakpoint(&mut self) {
+        self.mode = Mode::Continue;
+    }
+
+    /// Write a line for the user. A broken terminal must not abort the
+    /// transaction being debugged, so write errors are ignored.
+    fn say(&mut self, text: &str) {
+        let _ = writeln!(self.output, "{}", text);
+    }
+
+    /// Read commands until one resumes execution.
+    fn prompt(&mut self, state: &WorldState) {
+        self.show_info();
+        loop {
+            let _ = write!(self.output, "(axisdbg) ");
+            let _ = self.output.flush();
+
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    // No more input: let the transaction finish
+                    self.mode = Mode::Detached;
+                    return;
+                }
+                Ok(_) => {}
+            }
+            if self.command(line.trim(), state) {
+                self.cursor = self.history.len().saturating_sub(1);
+                return;
+            }
+        }
+    }
+
+    /// Run one command; returns true if execution resumes.
+    fn command(&mut self, line: &str, state: &WorldState) -> bool {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => return false,
+        };
+        let args: Vec<&str> = words.collect();
+
+        match command {
+            "step" | "s" => {
+                self.mode = Mode::Step;
+                return true;
+            }
+            "continue" | "c" => {
+                self.mode = Mode::Continue;
+                return true;
+            }
+            "detach" => {
+                self.mode = Mode::Detached;
+                return true;
+            }
+            "break" | "b" | "delete" => match args.first().and_then(|arg| parse_breakpoint(arg)) {
+                Some(breakpoint) if command == "delete" => {
+                    self.breakpoints.remove(&breakpoint);
+                }
+                Some(breakpoint) => {
+                    self.breakpoints.insert(breakpoint);
+                }
+                None => self.say("expected a pc or an opcode mnemonic"),
+            },
+            "watch" | "w" | "unwatch" => self.watch_command(command == "unwatch", &args, state),
+            "info" | "i" => self.show_info(),
+            "stack" => self.show_stack(),
+            "memory" | "m" => {
+                let offset = args.first().and_then(|arg| parse_number(arg)).unwrap_or(0);
+                let len = args.get(1).and_then(|arg| parse_number(arg));
+                self.show_memory(offset, len);
+            }
+            "storage" => match args.first().and_then(|arg| parse_u256(arg)) {
+                Some(key) => {
+                    let address = match self.current() {
+                        Some(snapshot) => snapshot.address,
+                        None => return false,
+                    };
+                    let value = state.storage(address, key);
+                    self.say(&format!("{:#x}: {:#x}", key, value));
+                }
+                None => self.say("expected a storage key"),
+            },
+            "returndata" | "r" => {
+                let data = self.current().map(|snapshot| snapshot.return_data.clone());
+                let data = data.unwrap_or_default();
+                self.say(&hexdump(&data, 0));
+            }
+            "backtrace" | "bt" => {
+                let lines: Vec<String> = self
+                    .frames
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(depth, address)| format!("#{} {:?}", depth, address))
+                    .collect();
+                for line in lines {
+                    self.say(&line);
+                }
+            }
+            "back" | "forward" => {
+                let n = args.first().and_then(|arg| parse_number(arg)).unwrap_or(1);
+                let last = self.history.len().saturating_sub(1);
+                self.cursor = if command == "back" {
+                    self.cursor.saturating_sub(n)
+                } else {
+                    (self.cursor + n).min(last)
+                };
+                if self.cursor < last {
+                    let behind = last - self.cursor;
+                    self.say(&format!(
+                        "viewing {} step(s) back; storage shows the live state",
+                        behind
+                    ));
+                }
+                self.show_info();
+            }
+            "help" | "h" => self.say(HELP.trim_end()),
+            _ => self.say(&format!("unknown command `{}`, try `help`", command)),
+        }
+        false
+    }
+
+    /// Add or remove a watchpoint on the slot `args[0]` of `args[1]` or the
+    /// current account.
+    fn watch_command(&mut self, remove: bool, args: &[&str], state: &WorldState) {
+        let key = match args.first().and_then(|arg| parse_u256(arg)) {
+            Some(key) => key,
+            None => return self.say("expected a storage key"),
+        };
+        let address = match args.get(1) {
+            Some(arg) => match parse_u256(arg) {
+                Some(value) => {
+                    let mut bytes = [0u8; 32];
+                    value.to_big_endian(&mut bytes);
+                    H160::from_slice(&bytes[12..])
+                }
+                None => return self.say("expected an address"),
+            },
+            None => match self.current() {
+                Some(snapshot) => snapshot.address,
+                None => return,
+            },
+        };
+
+        if remove {
+            self.watchpoints.retain(|watch| watch.address != address || watch.key != key);
+        } else {
+            let value = state.storage(address, key);
+            self.watchpoints.push(Watchpoint { address, key, value });
+        }
+    }
+
+    /// Step being looked at
+    fn current(&self) -> Option<&Snapshot> {
+        self.history.get(self.cursor)
+    }
+
+    fn show_info(&mut self) {
+        let line = match self.current() {
+            Some(snapshot) => {
+                let mnemonic = match opcode::mnemonic(snapshot.opcode) {
+                    Some(mnemonic) => mnemonic.to_string(),
+                    None => format!("INVALID(0x{:02x})", snapshot.opcode),
+                };
+                format!(
+                    "pc 0x{:04x}  {}  gas {}  depth {}  address {:?}",
+                    snapshot.pc, mnemonic, snapshot.gas, snapshot.depth, snapshot.address
+                )
+            }
+            None => "not started".to_string(),
+        };
+        self.say(&line);
+    }
+
+    fn show_stack(&mut self) {
+        let lines: Vec<String> = match self.current() {
+            Some(snapshot) => snapshot
+                .stack
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(i, value)| format!("{:4}: {:#x}", i, value))
+                .collect(),
+            None => Vec::new(),
+        };
+        if lines.is_empty() {
+            self.say("stack is empty");
+        }
+        for line in lines {
+            self.say(&line);
+        }
+    }
+
+    fn show_memory(&mut self, offset: usize, len: Option<usize>) {
+        let dump = match self.current() {
+            Some(snapshot) => {
+                let start = offset.min(snapshot.memory.len());
+                let end = len.map_or(snapshot.memory.len(), |len| start.saturating_add(len));
+                let end = end.min(snapshot.memory.len());
+                hexdump(&snapshot.memory[start..end], start)
+            }
+            None => String::new(),
+        };
+        self.say(&dump);
+    }
+
+    /// Returns true if a breakpoint is set on the instruction of `snapshot`.
+    fn at_breakpoint(&self, snapshot: &Snapshot) -> bool {
+        self.breakpoints.contains(&Breakpoint::Pc(snapshot.pc))
+            || self.breakpoints.contains(&Breakpoint::Opcode(snapshot.opcode))
+    }
+}
+
+impl<R: BufRead, W: Write> Inspector for Debugger<R, W> {
+    fn step(&mut self, vm: &AXISVM, state: &WorldState) {
+        if self.mode == Mode::Detached {
+            return;
+        }
+
+        if self.frames.is_empty() {
+            self.frames.push(vm.address());
+        }
+        let mut snapshot = Snapshot {
+            pc: vm.pc(),
+            opcode: vm.opcode().unwrap_or(0),
+            gas: vm.gas(),
+            depth: vm.depth(),
+            address: vm.address(),
+            stack: vm.stack().data().to_vec(),
+            memory: Rc::default(),
+            return_data: Rc::default(),
+        };
+        let previous = self
+            .history
+            .back()
+            .filter(|previous| previous.precedes_in_frame(&snapshot));
+        snapshot.memory = match previous {
+            Some(previous)
+                if !writes_memory(previous.opcode)
+                    && previous.memory.len() == vm.memory().data().len() =>
+            {
+                previous.memory.clone()
+            }
+            _ => {
+                self.history_bytes += vm.memory().data().len();
+                Rc::new(vm.memory().data().to_vec())
+            }
+        };
+        snapshot.return_data = match previous {
+            Some(previous) if !sets_return_data(previous.opcode) => previous.return_data.clone(),
+            _ => {
+                self.history_bytes += vm.return_data().len();
+                Rc::new(vm.return_data().to_vec())
+            }
+        };
+        self.history_bytes += 32 * snapshot.stack.len();
+        let stop = match self.mode {
+            Mode::Step => true,
+            Mode::Continue => self.watch_hit || self.at_breakpoint(&snapshot),
+            Mode::Detached => false,
+        };
+
+        self.history.push_back(snapshot);
+        self.trim_history();
+        self.cursor = self.history.len() - 1;
+
+        if stop {
+            self.watch_hit = false;
+            self.prompt(state);
+        }
+    }
+
+    fn step_end(&mut self, _vm: &AXISVM, state: &WorldState, result: &Result<(), Halt>) {
+        if self.mode == Mode::Detached {
+            return;
+        }
+
+        let mut changes = Vec::new();
+        for watch in &mut self.watchpoints {
+            let value = state.storage(watch.address, watch.key);
+            if value != watch.value {
+                changes.push(format!(
+                    "watchpoint {:?}[{:#x}]: {:#x} -> {:#x}",
+                    watch.address, watch.key, watch.value, value
+                ));
+                watch.value = value;
+            }
+        }
+        for change in changes {
+            self.say(&change);
+            self.watch_hit = true;
+        }
+
+        match *result {
+            Ok(()) | Err(Halt::Stop) | Err(Halt::Return) => {}
+            Err(halt) => {
+                // Stop where the frame failed, before its state is gone
+                self.say(&format!("frame halted: {:?}", halt));
+                self.prompt(state);
+            }
+        }
+    }
+
+    fn call(&mut self, _caller: &AXISVM, inputs: &CallInputs) {
+        self.frames.push(inputs.code_address);
+    }
+
+    fn call_end(&mut self, _inputs: &CallInputs, _result: &FrameResult) {
+        self.frames.pop();
+    }
+
+    fn create(&mut self, _creator: &AXISVM, inputs: &CreateInputs) {
+        self.frames.push(inputs.address);
+    }
+
+    fn create_end(&mut self, _inputs: &CreateInputs, _result: &FrameResult) {
+        self.frames.pop();
+    }
+}
+
+/// Returns true if running `opcode` can write memory without growing it:
+/// the copies, stores and the output of a call
+fn writes_memory(opcode: u8) -> bool {
+    matches!(opcode, 0x37 | 0x39 | 0x3c | 0x3e | 0x52 | 0x53 | 0x5e | 0xf1 | 0xf2 | 0xf4 | 0xfa)
+}
+
+/// Returns true if running `opcode` sets the return data of its frame
+fn sets_return_data(opcode: u8) -> bool {
+    matches!(opcode, 0xf0 | 0xf1 | 0xf2 | 0xf4 | 0xf5 | 0xfa)
+}
+
+/// A code offset or an opcode mnemonic
+fn parse_breakpoint(arg: &str) -> Option<Breakpoint> {
+    match parse_number(arg) {
+        Some(pc) => Some(Breakpoint::Pc(pc)),
+        None => opcode::from_mnemonic(arg).map(Breakpoint::Opcode),
+    }
+}
+
+/// A decimal or `0x`-prefixed hex number
+fn parse_number(arg: &str) -> Option<usize> {
+    match arg.strip_prefix("0x") {
+        Some(digits) => usize::from_str_radix(digits, 16).ok(),
+        None => arg.parse().ok(),
+    }
+}
+
+/// A decimal or `0x`-prefixed hex word
+fn parse_u256(arg: &str) -> Option<U256> {
+    match arg.strip_prefix("0x") {
+        Some(digits) => U256::from_str_radix(digits, 16).ok(),
+        None => U256::from_dec_str(arg).ok(),
+    }
+}
+
+/// Hex and ASCII dump of `data`, 16 bytes per line, offsets starting at
+/// `offset`
+fn hexdump(data: &[u8], offset: usize) -> String {
+    if data.is_empty() {
+        return "(empty)".to_string();
+    }
+
+    let mut dump = String::new();
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if (0x20..0x7f).contains(&b) { b as char } else { '.' })
+            .collect();
+        if i > 0 {
+            dump.push('\n');
+        }
+        dump.push_str(&format!("0x{:04x}: {:<47}  |{}|", offset + 16 * i, hex.join(" "), ascii));
+    }
+    dump
+}
@@ -0,0 +1,201 @@
+//! Axis VM static disassembler
+//!
+//! Decodes bytecode linearly without running it, so every instruction is
+//! shown, not only the ones a particular execution reaches.
+
+use std::fmt;
+
+extern crate serde_json;
+
+use self::serde_json::{Map, Value};
+use super::opcode;
+
+/// A decoded piece of bytecode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// An assigned opcode. `immediate` holds the bytes of a PUSH; it is
+    /// shorter than the PUSH size if the code ends first.
+    Op { pc: usize, opcode: u8, immediate: Vec<u8> },
+    /// A byte that is not an assigned opcode
+    Invalid { pc: usize, byte: u8 },
+    /// Compiler metadata appended after the code (CBOR-encoded, with its
+    /// length in the last two bytes)
+    Metadata { pc: usize, data: Vec<u8> },
+}
+
+impl Instruction {
+    /// Offset of the instruction in the code
+    pub fn pc(&self) -> usize {
+        match *self {
+            Instruction::Op { pc, .. }
+            | Instruction::Invalid { pc, .. }
+            | Instruction::Metadata { pc, .. } => pc,
+        }
+    }
+
+    /// Number of code bytes the instruction occupies
+    pub fn size(&self) -> usize {
+        match *self {
+            Instruction::Op { ref immediate, .. } => 1 + immediate.len(),
+            Instruction::Invalid { .. } => 1,
+            Instruction::Metadata { ref data, .. } => data.len(),
+        }
+    }
+
+    /// JSON object describing the instruction
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("pc".to_string(), Value::from(self.pc()));
+        match *self {
+            Instruction::Op { opcode, ref immediate, .. } => {
+                object.insert("opcode".to_string(), Value::from(opcode));
+                object.insert("mnemonic".to_string(), Value::from(opcode::mnemonic(opcode)));
+                if opcode::immediate_len(opcode) > 0 {
+                    object.insert("immediate".to_string(), Value::from(hex(immediate)));
+                    if immediate.len() < opcode::immediate_len(opcode) {
+                        object.insert("truncated".to_string(), Value::from(true));
+                    }
+                }
+            }
+            Instruction::Invalid { byte, .. } => {
+                object.insert("opcode".to_string(), Value::from(byte));
+                object.insert("mnemonic".to_string(), Value::from("INVALID"));
+            }
+            Instruction::Metadata { ref data, .. } => {
+                object.insert("metadata".to_string(), Value::from(hex(data)));
+            }
+        }
+        Value::Object(object)
+    }
+}
+
+/// One line of assembly: `0x0000: PUSH1 0x80`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:04x}: ", self.pc())?;
+        match *self {
+            Instruction::Op { opcode, ref immediate, .. } => {
+                let mnemonic = opcode::mnemonic(opcode).expect("assigned opcode");
+                if opcode::immediate_len(opcode) == 0 {
+                    write!(f, "{}", mnemonic)
+                } else if immediate.len() < opcode::immediate_len(opcode) {
+                    write!(f, "{} {} (truncated)", mnemonic, hex(immediate))
+                } else {
+                    write!(f, "{} {}", mnemonic, hex(immediate))
+                }
+            }
+            Instruction::Invalid { byte, .. } => write!(f, "INVALID(0x{:02x})", byte),
+            Instruction::Metadata { ref data, .. } => write!(f, "METADATA {}", hex(data)),
+        }
+    }
+}
+
+/// `0x`-prefixed lowercase hex of `bytes`
+fn hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("0x{}", digits)
+}
+
+/// Offset where the compiler metadata trailing `code` starts, if any. The
+/// last two bytes give its length and it must start with a CBOR map on an
+/// instruction boundary: bytes that look like a trailer but begin inside a
+/// PUSH immediate are ordinary code.
+fn metadata_start(code: &[u8]) -> Option<usize> {
+    if code.len() < 2 {
+        return None;
+    }
+    let len = ((code[code.len() - 2] as usize) << 8) | code[code.len() - 1] as usize;
+    let start = code.len().checked_sub(len + 2)?;
+    match code[start..code.len() - 2].first() {
+        // A CBOR map with one to five entries
+        Some(&byte) if (0xa1..=0xa5).contains(&byte) => {}
+        _ => return None,
+    }
+
+    let mut pc = 0;
+    while pc < start {
+        pc += 1 + opcode::immediate_len(code[pc]);
+    }
+    if pc == start {
+        Some(start)
+    } else {
+        None
+    }
+}
+
+/// Decode `code` into instructions, from the first byte to the last.
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    let end = metadata_start(code).unwrap_or(code.len());
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+
+    while pc < end {
+        let byte = code[pc];
+        if opcode::mnemonic(byte).is_none() {
+            instructions.push(Instruction::Invalid { pc, byte });
+            pc += 1;
+            continue;
+        }
+
+        let immediate_end = (pc + 1 + opcode::immediate_len(byte)).min(end);
+        instructions.push(Instruction::Op {
+            pc,
+            opcode: byte,
+            immediate: code[pc + 1..immediate_end].to_vec(),
+        });
+        pc = immediate_end;
+    }
+
+    if end < code.len() {
+        instructions.push(Instruction::Metadata {
+            pc: end,
+            data: code[end..].to_vec(),
+        });
+    }
+    instructions
+}
+
+/// Assembly listing, one instruction per line
+pub fn to_text(instructions: &[Instruction]) -> String {
+    let mut text = String::new();
+    for instruction in instructions {
+        text.push_str(&instruction.to_string());
+        text.push('\n');
+    }
+    text
+}
+
+/// JSON array of the instructions
+pub fn to_json(instructions: &[Instruction]) -> Value {
+    Value::Array(instructions.iter().map(Instruction::to_json).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_on_instruction_boundary() {
+        let code = [0x00, 0xa1, 0x00, 0x01];
+        assert_eq!(
+            disassemble(&code),
+            vec![
+                Instruction::Op { pc: 0, opcode: 0x00, immediate: Vec::new() },
+                Instruction::Metadata { pc: 1, data: vec![0xa1, 0x00, 0x01] },
+            ]
+        );
+    }
+
+    #[test]
+    fn metadata_inside_push_is_code() {
+        let code = [0x60, 0xa1, 0x00, 0x01];
+        assert_eq!(
+            disassemble(&code),
+            vec![
+                Instruction::Op { pc: 0, opcode: 0x60, immediate: vec![0xa1] },
+                Instruction::Op { pc: 2, opcode: 0x00, immediate: Vec::new() },
+                Instruction::Op { pc: 3, opcode: 0x01, immediate: Vec::new() },
+            ]
+        );
+    }
+}
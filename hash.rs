@@ -0,0 +1,15 @@
+//! Axis VM hashing primitives
+
+extern crate tiny_keccak;
+
+use ethereum_types::H256;
+use tiny_keccak::{Hasher, Keccak};
+
+/// Keccak-256 digest of `data`, as used by SHA3 and for code hashes.
+pub fn keccak256(data: &[u8]) -> H256 {
+    let mut keccak = Keccak::v256();
+    let mut output = [0u8; 32];
+    keccak.update(data);
+    keccak.finalize(&mut output);
+    H256::from(output)
+}
@@ -0,0 +1,94 @@
+//! Axis VM execution hooks
+//!
+//! An `Inspector` is called by the interpreter around every instruction and
+//! nested frame, so tracers, profilers and debuggers can observe execution
+//! without living inside the interpreter loop. The interpreter is generic
+//! over the inspector; with `NoopInspector` every hook compiles away.
+
+use ethereum_types::{H160, U256};
+use super::log::Log;
+use super::vm::{CallKind, CreateKind, Halt, AXISVM};
+use super::world::WorldState;
+
+/// A message call about to run in a new frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallInputs<'a> {
+    pub kind: CallKind,
+    /// Account making the call
+    pub caller: H160,
+    /// Account whose code runs
+    pub code_address: H160,
+    /// Account whose storage and balance the code acts on
+    pub context_address: H160,
+    pub value: U256,
+    pub input: &'a [u8],
+    /// Gas given to the new frame
+    pub gas: usize,
+    /// Depth of the new frame
+    pub depth: usize,
+    pub is_static: bool,
+}
+
+/// A contract creation about to run its init code in a new frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreateInputs<'a> {
+    pub kind: CreateKind,
+    pub creator: H160,
+    /// Address the contract is created at
+    pub address: H160,
+    pub value: U256,
+    pub init_code: &'a [u8],
+    /// Gas given to the new frame
+    pub gas: usize,
+    /// Depth of the new frame
+    pub depth: usize,
+}
+
+/// How a nested frame ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameResult<'a> {
+    pub halt: Halt,
+    /// Gas the frame had left, returned to the caller unless it halted
+    /// exceptionally
+    pub gas_left: usize,
+    /// Data returned by RETURN or REVERT; for a successful creation, the
+    /// deployed code
+    pub output: &'a [u8],
+}
+
+/// Hooks called during execution. Every hook has an empty default, so an
+/// inspector only implements the ones it needs.
+pub trait Inspector {
+    /// Before the instruction at `vm.pc()` runs
+    fn step(&mut self, _vm: &AXISVM, _state: &WorldState) {}
+
+    /// After an instruction ran; `result` is `Err` if it halted the frame
+    fn step_end(&mut self, _vm: &AXISVM, _state: &WorldState, _result: &Result<(), Halt>) {}
+
+    /// Before a nested message call runs. `caller` is the calling frame,
+    /// already charged for the call and the gas given to it.
+    fn call(&mut self, _caller: &AXISVM, _inputs: &CallInputs) {}
+
+    /// After a nested message call ended
+    fn call_end(&mut self, _inputs: &CallInputs, _result: &FrameResult) {}
+
+    /// Before the init code of a new contract runs. `creator` is the
+    /// creating frame, already charged for the creation and the gas given
+    /// to it.
+    fn create(&mut self, _creator: &AXISVM, _inputs: &CreateInputs) {}
+
+    /// After the init code ran and its code was deployed or rejected
+    fn create_end(&mut self, _inputs: &CreateInputs, _result: &FrameResult) {}
+
+    /// A log was emitted
+    fn log(&mut self, _log: &Log) {}
+
+    /// `address` self-destructed, sending `value` to `beneficiary`
+    fn selfdestruct(&mut self, _address: H160, _beneficiary: H160, _value: U256) {}
+}
+
+/// Inspector that does nothing, used when execution is not observed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopInspector;
+
+impl Inspector for NoopInspector {}
@@ -0,0 +1,140 @@
+//! Axis state journal
+//!
+//! Every change made to the `WorldState` during a transaction is recorded
+//! here with the value it replaced, so that the effects of a reverted call
+//! frame can be undone in reverse order without touching anything that
+//! happened before it.
+
+use ethereum_types::{H160, H256, U256};
+use super::log::Log;
+use super::world::Account;
+
+/// A single undoable state change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    /// A storage slot was written; `previous` is its value before the write
+    StorageChanged { address: H160, key: U256, previous: U256 },
+    /// A transient storage slot was written (EIP-1153)
+    TransientStorageChanged { address: H160, key: U256, previous: U256 },
+    BalanceChanged { address: H160, previous: U256 },
+    NonceChanged { address: H160, previous: u64 },
+    CodeChanged { address: H160, previous_code: Vec<u8>, previous_hash: H256 },
+    /// The account did not exist before
+    AccountCreated { address: H160 },
+    /// The account was deleted; its storage is cleared when the transaction
+    /// commits
+    AccountDeleted { address: H160, account: Account },
+    /// The account was accessed for the first time in the transaction
+    /// (EIP-2929)
+    AccountWarmed { address: H160 },
+    /// The storage slot was accessed for the first time in the transaction
+    StorageWarmed { address: H160, key: U256 },
+    /// The account was touched for the first time in the transaction
+    /// (EIP-161)
+    AccountTouched { address: H160 },
+    /// A contract was created at the address by the transaction
+    ContractCreated { address: H160 },
+    /// The account self-destructed and is deleted when the transaction
+    /// commits
+    SelfDestructed { address: H160 },
+}
+
+/// Position in the journal to revert to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    entries: usize,
+    logs: usize,
+}
+
+/// Changes and logs of the running transaction
+#[derive(Debug, Default)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+    logs: Vec<Log>,
+}
+
+impl Journal {
+    /// Record a state change.
+    pub fn record(&mut self, entry: JournalEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Record an emitted log.
+    pub fn log(&mut self, log: Log) {
+        self.logs.push(log);
+    }
+
+    /// Logs emitted since the last commit.
+    pub fn logs(&self) -> &[Log] {
+        &self.logs
+    }
+
+    /// Mark the current position.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            entries: self.entries.len(),
+            logs: self.logs.len(),
+        }
+    }
+
+    /// Drop the logs emitted after `checkpoint` and return the state
+    /// changes made after it, most recent first, for the caller to undo.
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) -> Vec<JournalEntry> {
+        self.logs.truncate(checkpoint.logs);
+        let mut undone = self.entries.split_off(checkpoint.entries);
+        undone.reverse();
+        undone
+    }
+
+    /// Accounts deleted since the last commit
+    pub fn deleted_accounts(&self) -> Vec<H160> {
+        self.entries
+            .iter()
+            .filter_map(|entry| match *entry {
+                JournalEntry::AccountDeleted { address, .. } => Some(address),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Accounts whose balance, nonce or code changed, or that were created
+    /// or deleted, since the last commit
+    pub fn changed_accounts(&self) -> Vec<H160> {
+        let mut changed: Vec<H160> = self
+            .entries
+            .iter()
+            .filter_map(|entry| match *entry {
+                JournalEntry::BalanceChanged { address, .. }
+                | JournalEntry::NonceChanged { address, .. }
+                | JournalEntry::CodeChanged { address, .. }
+                | JournalEntry::AccountCreated { address }
+                | JournalEntry::AccountDeleted { address, .. } => Some(address),
+                _ => None,
+            })
+            .collect();
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    /// Accounts with storage written since the last commit
+    pub fn changed_storage(&self) -> Vec<H160> {
+        let mut changed: Vec<H160> = self
+            .entries
+            .iter()
+            .filter_map(|entry| match *entry {
+                JournalEntry::StorageChanged { address, .. } => Some(address),
+                _ => None,
+            })
+            .collect();
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    /// Forget every entry and hand over the logs of the finished transaction.
+    pub fn commit(&mut self) -> Vec<Log> {
+        self.entries.clear();
+        self.logs.drain(..).collect()
+    }
+}
@@ -0,0 +1,43 @@
+//! Axis event logs and bloom filters
+
+use ethereum_types::{Bloom, BloomInput, H160, H256};
+
+/// Log entry emitted by a contract with LOG0 - LOG4
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub address: H160,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+impl Log {
+    /// Add the address and topics of this log to `bloom`.
+    pub fn accrue_to(&self, bloom: &mut Bloom) {
+        bloom.accrue(BloomInput::Raw(self.address.as_bytes()));
+        for topic in &self.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+    }
+}
+
+/// 2048-bit bloom of the addresses and topics of a transaction's logs, as
+/// stored in its receipt.
+pub fn logs_bloom(logs: &[Log]) -> Bloom {
+    let mut bloom = Bloom::default();
+    for log in logs {
+        log.accrue_to(&mut bloom);
+    }
+    bloom
+}
+
+/// Bloom of a block: the union of the blooms of its receipts.
+pub fn block_bloom<'a, I>(receipt_blooms: I) -> Bloom
+where
+    I: IntoIterator<Item = &'a Bloom>,
+{
+    let mut bloom = Bloom::default();
+    for receipt_bloom in receipt_blooms {
+        bloom.accrue_bloom(receipt_bloom);
+    }
+    bloom
+}
@@ -0,0 +1,101 @@
+//! Axis VM memory
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use ethereum_types::U256;
+
+/// Number of 32-byte words needed to hold `len` bytes.
+#[inline]
+pub fn word_count(len: usize) -> usize {
+    (len + 31) / 32
+}
+
+/// Represents the linear, byte-addressed AxisVM memory. It only ever grows,
+/// always by whole 32-byte words, and new words are zero-filled.
+///
+/// Accessors do not grow the memory themselves: callers first `resize` to
+/// cover the range, so that the expansion gas is charged exactly once.
+#[derive(Debug, Default)]
+pub struct Memory {
+    data: Vec<u8>,
+}
+
+impl Memory {
+    /// Current memory size in bytes, always a multiple of 32.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns true if no memory has been touched yet
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Current memory size in words.
+    #[inline]
+    pub fn words(&self) -> usize {
+        self.data.len() / 32
+    }
+
+    /// Grow the memory to `words` words. Never shrinks.
+    pub fn resize(&mut self, words: usize) {
+        if words > self.words() {
+            self.data.resize(words * 32, 0);
+        }
+    }
+
+    /// The whole memory contents.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Borrow `len` bytes starting at `offset`.
+    pub fn get(&self, offset: usize, len: usize) -> &[u8] {
+        &self.data[offset..offset + len]
+    }
+
+    /// Overwrite memory at `offset` with `value`.
+    pub fn set(&mut self, offset: usize, value: &[u8]) {
+        self.data[offset..offset + value.len()].copy_from_slice(value);
+    }
+
+    /// Copy `len` bytes from `source` to `offset`. The ranges may overlap.
+    pub fn copy_within(&mut self, source: usize, offset: usize, len: usize) {
+        self.data.copy_within(source..source + len, offset);
+    }
+
+    /// Read the big-endian word at `offset`.
+    pub fn load_word(&self, offset: usize) -> U256 {
+        U256::from(self.get(offset, 32))
+    }
+
+    /// Write `value` as a big-endian word at `offset`.
+    pub fn store_word(&mut self, offset: usize, value: U256) {
+        let mut word = [0u8; 32];
+        value.to_big_endian(&mut word);
+        self.set(offset, &word);
+    }
+
+    /// Write the low byte of `value` at `offset`.
+    pub fn store_byte(&mut self, offset: usize, value: U256) {
+        self.data[offset] = value.low_u32() as u8;
+    }
+
+    /// Copy `len` bytes of `source` from `source_offset` to `offset`. Bytes
+    /// past the end of `source` are copied as zeros, as CODECOPY and
+    /// CALLDATACOPY require.
+    pub fn copy_padded(&mut self, offset: usize, source: &[u8], source_offset: U256, len: usize) {
+        let start = if source_offset > U256::from(source.len()) {
+            source.len()
+        } else {
+            source_offset.low_u64() as usize
+        };
+        let available = len.min(source.len() - start);
+
+        self.data[offset..offset + available].copy_from_slice(&source[start..start + available]);
+        for byte in &mut self.data[offset + available..offset + len] {
+            *byte = 0;
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Axis VM opcode table
+//!
+//! Mnemonics of every opcode known to any fork, shared by the disassembler
+//! and the assembler. Whether an opcode is valid under a given fork is up to
+//! `SpecId::has_opcode`.
+
+/// Mnemonic of each opcode, `None` for unassigned bytes
+static MNEMONICS: [Option<&'static str>; 256] = [
+    Some("STOP"), Some("ADD"), Some("MUL"), Some("SUB"),
+    Some("DIV"), Some("SDIV"), Some("MOD"), Some("SMOD"),
+    Some("ADDMOD"), Some("MULMOD"), Some("EXP"), Some("SIGNEXTEND"),
+    None, None, None, None,
+    Some("LT"), Some("GT"), Some("SLT"), Some("SGT"),
+    Some("EQ"), Some("ISZERO"), Some("AND"), Some("OR"),
+    Some("XOR"), Some("NOT"), Some("BYTE"), Some("SHL"),
+    Some("SHR"), Some("SAR"), None, None,
+    Some("SHA3"), None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    Some("ADDRESS"), Some("BALANCE"), Some("ORIGIN"), Some("CALLER"),
+    Some("CALLVALUE"), Some("CALLDATALOAD"), Some("CALLDATASIZE"), Some("CALLDATACOPY"),
+    Some("CODESIZE"), Some("CODECOPY"), Some("GASPRICE"), Some("EXTCODESIZE"),
+    Some("EXTCODECOPY"), Some("RETURNDATASIZE"), Some("RETURNDATACOPY"), Some("EXTCODEHASH"),
+    Some("BLOCKHASH"), Some("COINBASE"), Some("TIMESTAMP"), Some("NUMBER"),
+    Some("DIFFICULTY"), Some("GASLIMIT"), Some("CHAINID"), Some("SELFBALANCE"),
+    Some("BASEFEE"), Some("BLOBHASH"), Some("BLOBBASEFEE"), None,
+    None, None, None, None,
+    Some("POP"), Some("MLOAD"), Some("MSTORE"), Some("MSTORE8"),
+    Some("SLOAD"), Some("SSTORE"), Some("JUMP"), Some("JUMPI"),
+    Some("PC"), Some("MSIZE"), Some("GAS"), Some("JUMPDEST"),
+    Some("TLOAD"), Some("TSTORE"), Some("MCOPY"), Some("PUSH0"),
+    Some("PUSH1"), Some("PUSH2"), Some("PUSH3"), Some("PUSH4"),
+    Some("PUSH5"), Some("PUSH6"), Some("PUSH7"), Some("PUSH8"),
+    Some("PUSH9"), Some("PUSH10"), Some("PUSH11"), Some("PUSH12"),
+    Some("PUSH13"), Some("PUSH14"), Some("PUSH15"), Some("PUSH16"),
+    Some("PUSH17"), Some("PUSH18"), Some("PUSH19"), Some("PUSH20"),
+    Some("PUSH21"), Some("PUSH22"), Some("PUSH23"), Some("PUSH24"),
+    Some("PUSH25"), Some("PUSH26"), Some("PUSH27"), Some("PUSH28"),
+    Some("PUSH29"), Some("PUSH30"), Some("PUSH31"), Some("PUSH32"),
+    Some("DUP1"), Some("DUP2"), Some("DUP3"), Some("DUP4"),
+    Some("DUP5"), Some("DUP6"), Some("DUP7"), Some("DUP8"),
+    Some("DUP9"), Some("DUP10"), Some("DUP11"), Some("DUP12"),
+    Some("DUP13"), Some("DUP14"), Some("DUP15"), Some("DUP16"),
+    Some("SWAP1"), Some("SWAP2"), Some("SWAP3"), Some("SWAP4"),
+    Some("SWAP5"), Some("SWAP6"), Some("SWAP7"), Some("SWAP8"),
+    Some("SWAP9"), Some("SWAP10"), Some("SWAP11"), Some("SWAP12"),
+    Some("SWAP13"), Some("SWAP14"), Some("SWAP15"), Some("SWAP16"),
+    Some("LOG0"), Some("LOG1"), Some("LOG2"), Some("LOG3"),
+    Some("LOG4"), None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    None, None, None, None,
+    Some("CREATE"), Some("CALL"), Some("CALLCODE"), Some("RETURN"),
+    Some("DELEGATECALL"), Some("CREATE2"), None, None,
+    None, None, Some("STATICCALL"), None,
+    None, Some("REVERT"), Some("INVALID"), Some("SELFDESTRUCT"),
+];
+
+/// Mnemonic of `opcode`, if it is assigned
+#[inline]
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    MNEMONICS[opcode as usize]
+}
+
+/// Opcode of `mnemonic`, case-insensitive
+pub fn from_mnemonic(mnemonic: &str) -> Option<u8> {
+    let mnemonic = mnemonic.to_ascii_uppercase();
+    (0..256)
+        .find(|&opcode| MNEMONICS[opcode] == Some(&mnemonic[..]))
+        .map(|opcode| opcode as u8)
+}
+
+/// Number of immediate bytes following `opcode`: n for PUSHn, 0 otherwise
+#[inline]
+pub fn immediate_len(opcode: u8) -> usize {
+    if (0x60..=0x7f).contains(&opcode) {
+        (opcode - 0x5f) as usize
+    } else {
+        0
+    }
+}
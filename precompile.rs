@@ -0,0 +1,751 @@
+//! Axis VM precompiled contracts
+//!
+//! Calls to a precompile address run native code instead of contract code.
+//! The standard Ethereum precompiles live at 0x01 - 0x09; Axis-specific ones
+//! are added by implementing `Precompile` and registering them with
+//! `Precompiles::register`.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+extern crate bn;
+extern crate num_bigint;
+extern crate ripemd;
+extern crate secp256k1;
+extern crate sha2;
+
+use ethereum_types::{H160, U256};
+use self::num_bigint::BigUint;
+use self::ripemd::Ripemd160;
+use self::sha2::{Digest, Sha256};
+use super::hash;
+use super::memory;
+use super::spec::SpecId;
+
+/// A precompiled contract failed on its input. The call consumes all the
+/// gas forwarded to it, like an exceptional halt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecompileError {
+    /// The input is malformed, e.g. a point not on the curve
+    InvalidInput,
+}
+
+/// A contract implemented natively
+pub trait Precompile {
+    /// Gas charged for running on `input`
+    fn gas(&self, input: &[u8]) -> usize;
+
+    /// Output for `input`
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError>;
+}
+
+/// Precompiles by address
+#[derive(Clone, Default)]
+pub struct Precompiles {
+    contracts: HashMap<H160, Rc<dyn Precompile>>,
+}
+
+impl Precompiles {
+    /// The standard precompiles available under `spec`, priced by its rules
+    pub fn for_spec(spec: SpecId) -> Precompiles {
+        let mut precompiles = Precompiles::default();
+        precompiles.register(address(1), EcRecover);
+        precompiles.register(address(2), Sha256Hash);
+        precompiles.register(address(3), Ripemd160Hash);
+        precompiles.register(address(4), Identity);
+
+        if spec.enabled(SpecId::Byzantium) {
+            let istanbul = spec.enabled(SpecId::Istanbul);
+            precompiles.register(address(5), ModExp { eip2565: spec.enabled(SpecId::Berlin) });
+            precompiles.register(address(6), Bn128Add { gas: if istanbul { 150 } else { 500 } });
+            precompiles.register(address(7), Bn128Mul { gas: if istanbul { 6000 } else { 40000 } });
+            precompiles.register(
+                address(8),
+                Bn128Pairing {
+                    base_gas: if istanbul { 45000 } else { 100_000 },
+                    pair_gas: if istanbul { 34000 } else { 80000 },
+                },
+            );
+        }
+        if spec.enabled(SpecId::Istanbul) {
+            precompiles.register(address(9), Blake2F);
+        }
+
+        precompiles
+    }
+
+    /// Install `precompile` at `address`, replacing any precompile there.
+    pub fn register<P: Precompile + 'static>(&mut self, address: H160, precompile: P) {
+        self.contracts.insert(address, Rc::new(precompile));
+    }
+
+    /// Precompile at `address`, if any
+    pub fn get(&self, address: H160) -> Option<Rc<dyn Precompile>> {
+        self.contracts.get(&address).cloned()
+    }
+
+    pub fn contains(&self, address: H160) -> bool {
+        self.contracts.contains_key(&address)
+    }
+
+    /// Addresses of every registered precompile
+    pub fn addresses<'a>(&'a self) -> impl Iterator<Item = H160> + 'a {
+        self.contracts.keys().cloned()
+    }
+}
+
+/// Address 0x00..00n
+fn address(n: u8) -> H160 {
+    H160::from_low_u64_be(n as u64)
+}
+
+/// `input[offset..offset + len]`, zero-padded past the end of the input
+fn padded(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    if offset < input.len() {
+        let end = input.len().min(offset + len);
+        out[..end - offset].copy_from_slice(&input[offset..end]);
+    }
+    out
+}
+
+/// Gas of `base + word * words(input)`
+fn linear_gas(input: &[u8], base: usize, word: usize) -> usize {
+    base + word * memory::word_count(input.len())
+}
+
+/// 0x01: address that signed a message hash, zero-padded to a word. Invalid
+/// signatures produce empty output rather than an error.
+pub struct EcRecover;
+
+impl Precompile for EcRecover {
+    fn gas(&self, _input: &[u8]) -> usize {
+        3000
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        use self::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+        use self::secp256k1::{Message, Secp256k1};
+
+        let input = padded(input, 0, 128);
+        let v = U256::from(&input[32..64]);
+        if v != U256::from(27) && v != U256::from(28) {
+            return Ok(Vec::new());
+        }
+
+        let recovery_id = match RecoveryId::from_i32(v.low_u32() as i32 - 27) {
+            Ok(id) => id,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let signature = match RecoverableSignature::from_compact(&input[64..128], recovery_id) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let message = Message::from_slice(&input[..32]).expect("message hash is 32 bytes");
+
+        match Secp256k1::verification_only().recover_ecdsa(&message, &signature) {
+            Ok(public) => {
+                let public = public.serialize_uncompressed();
+                let mut out = vec![0u8; 32];
+                out[12..].copy_from_slice(&hash::keccak256(&public[1..])[12..]);
+                Ok(out)
+            }
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// 0x02: SHA-256 of the input
+pub struct Sha256Hash;
+
+impl Precompile for Sha256Hash {
+    fn gas(&self, input: &[u8]) -> usize {
+        linear_gas(input, 60, 12)
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        Ok(Sha256::digest(input).to_vec())
+    }
+}
+
+/// 0x03: RIPEMD-160 of the input, zero-padded to a word
+pub struct Ripemd160Hash;
+
+impl Precompile for Ripemd160Hash {
+    fn gas(&self, input: &[u8]) -> usize {
+        linear_gas(input, 600, 120)
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        let mut out = vec![0u8; 32];
+        out[12..].copy_from_slice(&Ripemd160::digest(input));
+        Ok(out)
+    }
+}
+
+/// 0x04: the input itself
+pub struct Identity;
+
+impl Precompile for Identity {
+    fn gas(&self, input: &[u8]) -> usize {
+        linear_gas(input, 15, 3)
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        Ok(input.to_vec())
+    }
+}
+
+/// 0x05: `base ^ exponent % modulus` of arbitrary length integers
+/// (EIP-198), priced by EIP-2565 since Berlin.
+pub struct ModExp {
+    pub eip2565: bool,
+}
+
+impl ModExp {
+    /// Lengths of base, exponent and modulus from the input header
+    fn lengths(input: &[u8]) -> (U256, U256, U256) {
+        let header = padded(input, 0, 96);
+        (
+            U256::from(&header[..32]),
+            U256::from(&header[32..64]),
+            U256::from(&header[64..]),
+        )
+    }
+
+    /// Multiplication cost of operands of `len` bytes
+    fn complexity(&self, len: U256) -> U256 {
+        if self.eip2565 {
+            let words = (len + 7) / 8;
+            return words * words;
+        }
+        let x = len;
+        if x <= U256::from(64) {
+            x * x
+        } else if x <= U256::from(1024) {
+            x * x / 4 + U256::from(96) * x - U256::from(3072)
+        } else {
+            x * x / 16 + U256::from(480) * x - U256::from(199_680)
+        }
+    }
+
+    /// Number of squarings of the exponentiation, from the exponent length
+    /// and its first 32 bytes
+    fn iterations(input: &[u8], base_len: U256, exp_len: U256) -> U256 {
+        let head_len = exp_len.min(U256::from(32)).low_u64() as usize;
+        let head = if head_len == 0 || base_len > U256::from(u32::max_value()) {
+            U256::zero()
+        } else {
+            let mut head = padded(input, 96 + base_len.low_u64() as usize, head_len);
+            head.resize(32, 0);
+            U256::from(&head[..]) >> (8 * (32 - head_len))
+        };
+        let head_bits = if head.is_zero() { 0 } else { head.bits() - 1 };
+
+        if exp_len <= U256::from(32) {
+            U256::from(head_bits)
+        } else {
+            U256::from(8) * (exp_len - U256::from(32)) + U256::from(head_bits)
+        }
+    }
+}
+
+impl Precompile for ModExp {
+    fn gas(&self, input: &[u8]) -> usize {
+        let (base_len, exp_len, mod_len) = ModExp::lengths(input);
+        // Lengths this large cannot be paid for with any gas limit
+        let limit = U256::from(u32::max_value());
+        if base_len > limit || exp_len > limit || mod_len > limit {
+            return usize::max_value();
+        }
+
+        let complexity = self.complexity(base_len.max(mod_len));
+        let iterations = ModExp::iterations(input, base_len, exp_len).max(U256::one());
+        let gas = if self.eip2565 {
+            (complexity * iterations / 3).max(U256::from(200))
+        } else {
+            complexity * iterations / 20
+        };
+        if gas > U256::from(usize::max_value()) {
+            usize::max_value()
+        } else {
+            gas.low_u64() as usize
+        }
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        // The gas already bounds the lengths to u32
+        let (base_len, exp_len, mod_len) = ModExp::lengths(input);
+        let base_len = base_len.low_u64() as usize;
+        let exp_len = exp_len.low_u64() as usize;
+        let mod_len = mod_len.low_u64() as usize;
+        if mod_len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let base = BigUint::from_bytes_be(&padded(input, 96, base_len));
+        let exponent = BigUint::from_bytes_be(&padded(input, 96 + base_len, exp_len));
+        let modulus = BigUint::from_bytes_be(&padded(input, 96 + base_len + exp_len, mod_len));
+
+        let mut out = vec![0u8; mod_len];
+        if modulus != BigUint::from(0u8) {
+            let result = base.modpow(&exponent, &modulus).to_bytes_be();
+            out[mod_len - result.len()..].copy_from_slice(&result);
+        }
+        Ok(out)
+    }
+}
+
+/// Point of alt_bn128 G1 from 64 bytes of big-endian coordinates; (0, 0) is
+/// the point at infinity.
+fn read_g1(input: &[u8]) -> Result<bn::G1, PrecompileError> {
+    use self::bn::{AffineG1, Fq, Group, G1};
+
+    let x = Fq::from_slice(&input[..32]).map_err(|_| PrecompileError::InvalidInput)?;
+    let y = Fq::from_slice(&input[32..64]).map_err(|_| PrecompileError::InvalidInput)?;
+    if x.is_zero() && y.is_zero() {
+        Ok(G1::zero())
+    } else {
+        AffineG1::new(x, y)
+            .map(Into::into)
+            .map_err(|_| PrecompileError::InvalidInput)
+    }
+}
+
+/// Point of alt_bn128 G2 from 128 bytes, each coordinate encoded as its
+/// imaginary part followed by its real part
+fn read_g2(input: &[u8]) -> Result<bn::G2, PrecompileError> {
+    use self::bn::{AffineG2, Fq, Fq2, Group, G2};
+
+    let fq = |bytes: &[u8]| Fq::from_slice(bytes).map_err(|_| PrecompileError::InvalidInput);
+    let x = Fq2::new(fq(&input[32..64])?, fq(&input[..32])?);
+    let y = Fq2::new(fq(&input[96..128])?, fq(&input[64..96])?);
+    if x.is_zero() && y.is_zero() {
+        Ok(G2::zero())
+    } else {
+        AffineG2::new(x, y)
+            .map(Into::into)
+            .map_err(|_| PrecompileError::InvalidInput)
+    }
+}
+
+/// 64-byte encoding of a G1 point, all zero for the point at infinity
+fn write_g1(point: bn::G1) -> Vec<u8> {
+    let mut out = vec![0u8; 64];
+    if let Some(point) = bn::AffineG1::from_jacobian(point) {
+        point.x().to_big_endian(&mut out[..32]).expect("32-byte slice");
+        point.y().to_big_endian(&mut out[32..]).expect("32-byte slice");
+    }
+    out
+}
+
+/// 0x06: sum of two alt_bn128 G1 points (EIP-196)
+pub struct Bn128Add {
+    pub gas: usize,
+}
+
+impl Precompile for Bn128Add {
+    fn gas(&self, _input: &[u8]) -> usize {
+        self.gas
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        let input = padded(input, 0, 128);
+        let a = read_g1(&input[..64])?;
+        let b = read_g1(&input[64..])?;
+        Ok(write_g1(a + b))
+    }
+}
+
+/// 0x07: product of an alt_bn128 G1 point and a scalar (EIP-196)
+pub struct Bn128Mul {
+    pub gas: usize,
+}
+
+impl Precompile for Bn128Mul {
+    fn gas(&self, _input: &[u8]) -> usize {
+        self.gas
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        let input = padded(input, 0, 96);
+        let point = read_g1(&input[..64])?;
+        let scalar = bn::Fr::from_slice(&input[64..]).map_err(|_| PrecompileError::InvalidInput)?;
+        Ok(write_g1(point * scalar))
+    }
+}
+
+/// 0x08: pairing check of pairs of G1 and G2 points (EIP-197). Returns 1
+/// if the product of the pairings is one, 0 otherwise.
+pub struct Bn128Pairing {
+    pub base_gas: usize,
+    pub pair_gas: usize,
+}
+
+impl Precompile for Bn128Pairing {
+    fn gas(&self, input: &[u8]) -> usize {
+        self.base_gas + self.pair_gas * (input.len() / 192)
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        if input.len() % 192 != 0 {
+            return Err(PrecompileError::InvalidInput);
+        }
+
+        let mut pairs = Vec::with_capacity(input.len() / 192);
+        for pair in input.chunks(192) {
+            pairs.push((read_g1(&pair[..64])?, read_g2(&pair[64..])?));
+        }
+
+        let mut out = vec![0u8; 32];
+        if bn::pairing_batch(&pairs) == bn::Gt::one() {
+            out[31] = 1;
+        }
+        Ok(out)
+    }
+}
+
+/// 0x09: the BLAKE2b compression function F (EIP-152)
+pub struct Blake2F;
+
+/// BLAKE2b initialisation vector
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+/// Message word schedule of each BLAKE2b round
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Length of a BLAKE2F input: rounds, h, m, t and the final block flag
+const BLAKE2F_INPUT_LEN: usize = 4 + 64 + 128 + 16 + 1;
+
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(word)
+}
+
+/// The BLAKE2b mixing function G
+fn blake2b_mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Compress message block `m` into state `h` with `rounds` rounds
+fn blake2b_compress(rounds: u32, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], last: bool) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if last {
+        v[14] = !v[14];
+    }
+
+    for round in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        blake2b_mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        blake2b_mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        blake2b_mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        blake2b_mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        blake2b_mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        blake2b_mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        blake2b_mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        blake2b_mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+impl Precompile for Blake2F {
+    /// One gas per round
+    fn gas(&self, input: &[u8]) -> usize {
+        if input.len() != BLAKE2F_INPUT_LEN {
+            return 0;
+        }
+        u32::from_be_bytes([input[0], input[1], input[2], input[3]]) as usize
+    }
+
+    fn execute(&self, input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+        if input.len() != BLAKE2F_INPUT_LEN || input[212] > 1 {
+            return Err(PrecompileError::InvalidInput);
+        }
+
+        let rounds = u32::from_be_bytes([input[0], input[1], input[2], input[3]]);
+        let mut h = [0u64; 8];
+        for (i, word) in h.iter_mut().enumerate() {
+            *word = read_u64_le(&input[4 + 8 * i..]);
+        }
+        let mut m = [0u64; 16];
+        for (i, word) in m.iter_mut().enumerate() {
+            *word = read_u64_le(&input[68 + 8 * i..]);
+        }
+        let t = [read_u64_le(&input[196..]), read_u64_le(&input[204..])];
+
+        blake2b_compress(rounds, &mut h, &m, t, input[212] == 1);
+
+        let mut out = Vec::with_capacity(64);
+        for word in h.iter() {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// Two 32-byte words of a G1 point
+    const G1: &str = "0000000000000000000000000000000000000000000000000000000000000001\
+                      0000000000000000000000000000000000000000000000000000000000000002";
+    const NEG_G1: &str = "0000000000000000000000000000000000000000000000000000000000000001\
+                          30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45";
+    /// Generator of G2, imaginary part of each coordinate first
+    const G2: &str = "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+                      1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+                      090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+                      12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+    #[test]
+    fn ecrecover() {
+        let input = hex(
+            "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c\
+             000000000000000000000000000000000000000000000000000000000000001c\
+             73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f\
+             eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549",
+        );
+        assert_eq!(EcRecover.gas(&input), 3000);
+        assert_eq!(
+            EcRecover.execute(&input),
+            Ok(hex("000000000000000000000000a94f5374fce5edbc8e2a8697c15331677e6ebf0b"))
+        );
+
+        // v other than 27 or 28
+        let mut bad = input.clone();
+        bad[63] = 29;
+        assert_eq!(EcRecover.execute(&bad), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn sha256() {
+        assert_eq!(Sha256Hash.gas(b""), 60);
+        assert_eq!(Sha256Hash.gas(b"abc"), 72);
+        assert_eq!(
+            Sha256Hash.execute(b""),
+            Ok(hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"))
+        );
+        assert_eq!(
+            Sha256Hash.execute(b"abc"),
+            Ok(hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"))
+        );
+    }
+
+    #[test]
+    fn ripemd160() {
+        assert_eq!(Ripemd160Hash.gas(b""), 600);
+        assert_eq!(Ripemd160Hash.gas(b"abc"), 720);
+        assert_eq!(
+            Ripemd160Hash.execute(b""),
+            Ok(hex("0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31"))
+        );
+        assert_eq!(
+            Ripemd160Hash.execute(b"abc"),
+            Ok(hex("0000000000000000000000008eb208f7e05d987a9b044a8e98c6b087f15a0bfc"))
+        );
+    }
+
+    #[test]
+    fn identity() {
+        let input = hex("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef");
+        assert_eq!(Identity.gas(b""), 15);
+        assert_eq!(Identity.gas(&input), 18);
+        assert_eq!(Identity.execute(&input), Ok(input));
+    }
+
+    #[test]
+    fn modexp() {
+        // 3 ^ (p - 1) mod p for p = 2^256 - 2^32 - 977, the example of EIP-198
+        let input = hex(
+            "0000000000000000000000000000000000000000000000000000000000000001\
+             0000000000000000000000000000000000000000000000000000000000000020\
+             0000000000000000000000000000000000000000000000000000000000000020\
+             03\
+             fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e\
+             fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        );
+        let eip198 = ModExp { eip2565: false };
+        let eip2565 = ModExp { eip2565: true };
+        assert_eq!(eip198.gas(&input), 13056);
+        assert_eq!(eip2565.gas(&input), 1360);
+        assert_eq!(
+            eip2565.execute(&input),
+            Ok(hex("0000000000000000000000000000000000000000000000000000000000000001"))
+        );
+
+        // A zero modulus gives zero, an empty one no output at all
+        let zero = hex(
+            "0000000000000000000000000000000000000000000000000000000000000001\
+             0000000000000000000000000000000000000000000000000000000000000001\
+             0000000000000000000000000000000000000000000000000000000000000001\
+             030500",
+        );
+        assert_eq!(eip2565.gas(&zero), 200);
+        assert_eq!(eip2565.execute(&zero), Ok(vec![0]));
+        assert_eq!(eip2565.execute(&[]), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn bn128_add() {
+        let add = Bn128Add { gas: 150 };
+        let input = hex(
+            "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+             063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+             07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+             06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7",
+        );
+        assert_eq!(add.gas(&input), 150);
+        assert_eq!(
+            add.execute(&input),
+            Ok(hex(
+                "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
+                 301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915"
+            ))
+        );
+
+        // Infinity plus infinity, from empty input
+        assert_eq!(add.execute(&[]), Ok(vec![0; 64]));
+
+        // (1, 3) is not on the curve
+        let mut bad = hex(G1);
+        bad[63] = 3;
+        assert_eq!(add.execute(&bad), Err(PrecompileError::InvalidInput));
+    }
+
+    #[test]
+    fn bn128_mul() {
+        let mul = Bn128Mul { gas: 6000 };
+        let mut input = hex(
+            "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+             063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266",
+        );
+        input.extend(hex("0000000000000000000000000000000000000000000000000000000000000009"));
+        assert_eq!(mul.gas(&input), 6000);
+        assert_eq!(
+            mul.execute(&input),
+            Ok(hex(
+                "12201a91f428f54802c736818de4733c1487b14ed19a7b3092a540be8986cbb0\
+                 02cf906cc327480b1d2724ce00b8a87d4299bfc7aac8a2d53cdac58a72e02065"
+            ))
+        );
+
+        let mut double = hex(G1);
+        double.extend(hex("0000000000000000000000000000000000000000000000000000000000000002"));
+        assert_eq!(
+            mul.execute(&double),
+            Ok(hex(
+                "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+                 15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4"
+            ))
+        );
+    }
+
+    #[test]
+    fn bn128_pairing() {
+        let pairing = Bn128Pairing { base_gas: 45000, pair_gas: 34000 };
+        let one = hex("0000000000000000000000000000000000000000000000000000000000000001");
+        let zero = vec![0u8; 32];
+
+        // e(G1, G2) * e(-G1, G2) = 1
+        let mut input = hex(G1);
+        input.extend(hex(G2));
+        input.extend(hex(NEG_G1));
+        input.extend(hex(G2));
+        assert_eq!(pairing.gas(&input), 45000 + 2 * 34000);
+        assert_eq!(pairing.execute(&input), Ok(one.clone()));
+
+        assert_eq!(pairing.execute(&input[..192]), Ok(zero));
+        assert_eq!(pairing.gas(&[]), 45000);
+        assert_eq!(pairing.execute(&[]), Ok(one));
+        assert_eq!(pairing.execute(&input[..191]), Err(PrecompileError::InvalidInput));
+    }
+
+    /// Input of the EIP-152 test vectors: compression of the single block
+    /// "abc" with `rounds` rounds
+    fn blake2f_input(rounds: u32, last: u8) -> Vec<u8> {
+        let mut input = rounds.to_be_bytes().to_vec();
+        input.extend(hex(
+            "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+             d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b",
+        ));
+        let mut m = b"abc".to_vec();
+        m.resize(128, 0);
+        input.extend(m);
+        input.extend(hex("03000000000000000000000000000000"));
+        input.push(last);
+        assert_eq!(input.len(), BLAKE2F_INPUT_LEN);
+        input
+    }
+
+    #[test]
+    fn blake2f() {
+        let vectors = [
+            (
+                blake2f_input(0, 1),
+                "08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+                 d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b",
+            ),
+            (
+                blake2f_input(12, 1),
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                 7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+            ),
+            (
+                blake2f_input(12, 0),
+                "75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d28752\
+                 98743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735",
+            ),
+            (
+                blake2f_input(1, 1),
+                "b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fb\
+                 a551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421",
+            ),
+        ];
+        for &(ref input, output) in vectors.iter() {
+            assert_eq!(Blake2F.execute(input), Ok(hex(output)));
+        }
+        assert_eq!(Blake2F.gas(&vectors[1].0), 12);
+
+        let input = blake2f_input(12, 1);
+        assert_eq!(Blake2F.execute(&[]), Err(PrecompileError::InvalidInput));
+        assert_eq!(Blake2F.execute(&input[1..]), Err(PrecompileError::InvalidInput));
+        let mut long = input.clone();
+        long.push(0);
+        assert_eq!(Blake2F.execute(&long), Err(PrecompileError::InvalidInput));
+        assert_eq!(Blake2F.execute(&blake2f_input(12, 2)), Err(PrecompileError::InvalidInput));
+    }
+}
@@ -0,0 +1,290 @@
+//! Axis VM hard-fork specifications
+//!
+//! A `SpecId` decides which opcodes exist and, through its `GasSchedule`,
+//! what every instruction costs. Ethereum forks are kept so historical
+//! transactions replay with their original gas; `Axis` is the chain's own
+//! schedule and can be replaced at runtime without code changes.
+
+extern crate serde_json;
+
+use serde::{Deserialize, Serialize};
+
+/// Hard forks, oldest first. Each one includes every rule of the forks
+/// before it. Tangerine Whistle and Spurious Dragon are folded into
+/// Byzantium, Petersburg into Constantinople.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SpecId {
+    Frontier,
+    Homestead,
+    Byzantium,
+    Constantinople,
+    Istanbul,
+    Berlin,
+    London,
+    Shanghai,
+    Cancun,
+    Axis,
+}
+
+impl Default for SpecId {
+    fn default() -> SpecId {
+        SpecId::Axis
+    }
+}
+
+impl SpecId {
+    /// Returns true if the rules of `fork` apply under this spec.
+    #[inline]
+    pub fn enabled(self, fork: SpecId) -> bool {
+        self >= fork
+    }
+
+    /// Returns true if `opcode` exists under this spec. Opcodes missing from
+    /// every fork are handled by the interpreter as invalid.
+    pub fn has_opcode(self, opcode: u8) -> bool {
+        self.enabled(introduced_in(opcode))
+    }
+
+    /// Gas schedule of this fork
+    pub fn schedule(self) -> GasSchedule {
+        GasSchedule::for_spec(self)
+    }
+}
+
+/// Fork that introduced `opcode`; Frontier for the original instruction set.
+pub fn introduced_in(opcode: u8) -> SpecId {
+    match opcode {
+        0xf4 => SpecId::Homestead,
+        0x3d | 0x3e | 0xfa | 0xfd => SpecId::Byzantium,
+        0x1b | 0x1c | 0x1d | 0x3f | 0xf5 => SpecId::Constantinople,
+        0x46 | 0x47 => SpecId::Istanbul,
+        0x48 => SpecId::London,
+        0x5f => SpecId::Shanghai,
+        0x49 | 0x4a | 0x5c | 0x5d | 0x5e => SpecId::Cancun,
+        _ => SpecId::Frontier,
+    }
+}
+
+/// Gas costs and gas-related rules of a fork
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasSchedule {
+    /// ADDRESS, CALLER, PC, GAS, block information, ...
+    pub base: usize,
+    /// ADD, SUB, comparisons, bitwise operations, PUSH, DUP, SWAP, MLOAD, ...
+    pub very_low: usize,
+    /// MUL, DIV, MOD, SIGNEXTEND, SELFBALANCE
+    pub low: usize,
+    /// ADDMOD, MULMOD, JUMP
+    pub mid: usize,
+    /// JUMPI, EXP base
+    pub high: usize,
+    pub jumpdest: usize,
+    pub blockhash: usize,
+    /// EXP, per byte of exponent
+    pub exp_byte: usize,
+    pub sha3: usize,
+    pub sha3_word: usize,
+    /// CALLDATACOPY, CODECOPY, EXTCODECOPY, RETURNDATACOPY, MCOPY, per word
+    pub copy_word: usize,
+    pub memory_word: usize,
+    pub memory_quad_divisor: usize,
+    pub balance: usize,
+    pub ext_code_size: usize,
+    pub ext_code_copy: usize,
+    pub ext_code_hash: usize,
+    pub sload: usize,
+    pub sstore_set: usize,
+    pub sstore_reset: usize,
+    pub sstore_clears_refund: i64,
+    /// EIP-2200 net gas metering of SSTORE (Istanbul); legacy rules otherwise
+    pub sstore_net_metering: bool,
+    pub sstore_sentry: usize,
+    pub log: usize,
+    pub log_topic: usize,
+    pub log_data: usize,
+    pub call: usize,
+    pub call_value: usize,
+    pub call_new_account: usize,
+    pub call_stipend: usize,
+    /// EIP-150: forward at most all but one 64th of the remaining gas
+    pub call_all_but_one_64th: bool,
+    /// EIP-161: only empty accounts count as new for CALL and SELFDESTRUCT
+    pub empty_account_is_dead: bool,
+    /// EIP-161: new contracts start with nonce 1 instead of 0
+    pub create_nonce_one: bool,
+    /// EIP-2: a creation that cannot pay for its code deposit fails; before
+    /// it, the contract is created without code
+    pub code_deposit_oog_fails: bool,
+    pub create: usize,
+    pub create2_word: usize,
+    pub code_deposit_byte: usize,
+    /// EIP-170 limit on deployed code
+    pub max_code_size: Option<usize>,
+    /// EIP-3860 limit on the init code of CREATE and CREATE2. The VM runs
+    /// no contract-creation transactions, so their part of the EIP (the
+    /// intrinsic per-word cost) does not apply.
+    pub max_initcode_size: Option<usize>,
+    /// EIP-3860, per word of init code of CREATE and CREATE2
+    pub initcode_word: usize,
+    /// EIP-3541: reject deployed code starting with 0xef
+    pub reject_ef_code: bool,
+    /// EIP-2929: charge the prices above for the first access to an
+    /// account or slot in the transaction and `warm_access` afterwards
+    pub access_lists: bool,
+    pub warm_access: usize,
+    /// Extra gas of SELFDESTRUCT to a cold beneficiary
+    pub cold_account_access: usize,
+    /// EIP-3651: the coinbase is warm from the start of the transaction
+    pub warm_coinbase: bool,
+    /// TLOAD and TSTORE of transient storage (EIP-1153)
+    pub tload: usize,
+    pub tstore: usize,
+    pub selfdestruct: usize,
+    pub selfdestruct_new_account: usize,
+    pub selfdestruct_refund: i64,
+    /// EIP-6780: SELFDESTRUCT only deletes accounts created by the same
+    /// transaction; others just send their balance
+    pub selfdestruct_only_created: bool,
+    /// The refund is capped at gas used / this
+    pub max_refund_quotient: usize,
+}
+
+impl GasSchedule {
+    /// Schedule of `spec`
+    pub fn for_spec(spec: SpecId) -> GasSchedule {
+        let mut schedule = GasSchedule::frontier();
+
+        if spec.enabled(SpecId::Homestead) {
+            schedule.code_deposit_oog_fails = true;
+        }
+        if spec.enabled(SpecId::Byzantium) {
+            // Tangerine Whistle (EIP-150) and Spurious Dragon (EIP-160, 161, 170)
+            schedule.balance = 400;
+            schedule.ext_code_size = 700;
+            schedule.ext_code_copy = 700;
+            schedule.sload = 200;
+            schedule.call = 700;
+            schedule.call_all_but_one_64th = true;
+            schedule.selfdestruct = 5000;
+            schedule.selfdestruct_new_account = 25000;
+            schedule.exp_byte = 50;
+            schedule.empty_account_is_dead = true;
+            schedule.create_nonce_one = true;
+            schedule.max_code_size = Some(0x6000);
+        }
+        if spec.enabled(SpecId::Constantinople) {
+            schedule.ext_code_hash = 400;
+        }
+        if spec.enabled(SpecId::Istanbul) {
+            // EIP-1884 and EIP-2200
+            schedule.balance = 700;
+            schedule.ext_code_hash = 700;
+            schedule.sload = 800;
+            schedule.sstore_net_metering = true;
+        }
+        if spec.enabled(SpecId::Berlin) {
+            // EIP-2929 prices of a first (cold) access
+            schedule.balance = 2600;
+            schedule.ext_code_size = 2600;
+            schedule.ext_code_copy = 2600;
+            schedule.ext_code_hash = 2600;
+            schedule.call = 2600;
+            schedule.sload = 2100;
+            schedule.sstore_reset = 5000 - 2100;
+            schedule.access_lists = true;
+            schedule.warm_access = 100;
+            schedule.cold_account_access = 2600;
+        }
+        if spec.enabled(SpecId::London) {
+            // EIP-3529 and EIP-3541
+            schedule.sstore_clears_refund = 4800;
+            schedule.selfdestruct_refund = 0;
+            schedule.max_refund_quotient = 5;
+            schedule.reject_ef_code = true;
+        }
+        if spec.enabled(SpecId::Shanghai) {
+            // EIP-3651 and EIP-3860
+            schedule.warm_coinbase = true;
+            schedule.max_initcode_size = Some(2 * 0x6000);
+            schedule.initcode_word = 2;
+        }
+        if spec.enabled(SpecId::Cancun) {
+            // EIP-1153 and EIP-6780; MCOPY (EIP-5656) is priced like the
+            // other copies and BLOBHASH and BLOBBASEFEE like other
+            // environment reads
+            schedule.tload = 100;
+            schedule.tstore = 100;
+            schedule.selfdestruct_only_created = true;
+        }
+
+        schedule
+    }
+
+    /// Total gas charged for a memory of `words` words:
+    /// `memory_word * words + words^2 / memory_quad_divisor`.
+    pub fn memory_gas(&self, words: usize) -> usize {
+        self.memory_word * words + words * words / self.memory_quad_divisor
+    }
+
+    /// Load a schedule from JSON, e.g. a governance-approved Axis schedule.
+    pub fn from_json(json: &str) -> Result<GasSchedule, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    fn frontier() -> GasSchedule {
+        GasSchedule {
+            base: 2,
+            very_low: 3,
+            low: 5,
+            mid: 8,
+            high: 10,
+            jumpdest: 1,
+            blockhash: 20,
+            exp_byte: 10,
+            sha3: 30,
+            sha3_word: 6,
+            copy_word: 3,
+            memory_word: 3,
+            memory_quad_divisor: 512,
+            balance: 20,
+            ext_code_size: 20,
+            ext_code_copy: 20,
+            ext_code_hash: 0,
+            sload: 50,
+            sstore_set: 20000,
+            sstore_reset: 5000,
+            sstore_clears_refund: 15000,
+            sstore_net_metering: false,
+            sstore_sentry: 2300,
+            log: 375,
+            log_topic: 375,
+            log_data: 8,
+            call: 40,
+            call_value: 9000,
+            call_new_account: 25000,
+            call_stipend: 2300,
+            call_all_but_one_64th: false,
+            empty_account_is_dead: false,
+            create_nonce_one: false,
+            code_deposit_oog_fails: false,
+            create: 32000,
+            create2_word: 6,
+            code_deposit_byte: 200,
+            max_code_size: None,
+            max_initcode_size: None,
+            initcode_word: 0,
+            reject_ef_code: false,
+            access_lists: false,
+            warm_access: 0,
+            cold_account_access: 0,
+            warm_coinbase: false,
+            tload: 0,
+            tstore: 0,
+            selfdestruct: 0,
+            selfdestruct_new_account: 0,
+            selfdestruct_refund: 24000,
+            selfdestruct_only_created: false,
+            max_refund_quotient: 2,
+        }
+    }
+}
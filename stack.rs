@@ -5,13 +5,13 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-use bigint::M256;
+use ethereum_types::U256;
 use super::errors::OnChainError;
 
 /// Represents an AxisVM stack.
 #[derive(Debug)]
 pub struct Stack {
-    stack: Vec<M256>,
+    stack: Vec<U256>,
 }
 
 impl Default for Stack {
@@ -37,7 +37,7 @@ impl Stack {
 
 
         /// Pop a value from the stack.
-        pub fn pop(&mut self) -> Result<M256, OnChainError> {
+        pub fn pop(&mut self) -> Result<U256, OnChainError> {
             match self.stack.pop() {
                 Some(x) => Ok(x),
                 None => Err(OnChainError::StackUnderflow),
@@ -45,7 +45,7 @@ impl Stack {
         }
 
     /// Push a new value to the stack.
-    pub fn push(&mut self, elem: M256) -> Result<(), OnChainError> {
+    pub fn push(&mut self, elem: U256) -> Result<(), OnChainError> {
         self.stack.push(elem);
         if self.len() > 1024 {
             self.stack.pop();
@@ -60,7 +60,7 @@ impl Stack {
     /// Peek a value at given index for the stack, where the top of
     /// the stack is at index `0`. If the index is too large,
     /// `StackError::Underflow` is returned.
-    pub fn peek(&self, no_from_top: usize) -> Result<M256, OnChainError> {
+    pub fn peek(&self, no_from_top: usize) -> Result<U256, OnChainError> {
         if self.stack.len() > no_from_top {
             Ok(self.stack[self.stack.len() - no_from_top - 1])
         } else {
@@ -72,7 +72,7 @@ impl Stack {
     /// Set a value at given index for the stack, where the top of the
     /// stack is at index `0`. If the index is too large,
     /// `StackError::Underflow` is returned.
-    pub fn set(&mut self, no_from_top: usize, val: M256) -> Result<(), OnChainError> {
+    pub fn set(&mut self, no_from_top: usize, val: U256) -> Result<(), OnChainError> {
         if self.stack.len() > no_from_top {
             let len = self.stack.len();
             self.stack[len - no_from_top - 1] = val;
@@ -84,6 +84,11 @@ impl Stack {
 
 
 
+    /// Stack items, bottom first
+    pub fn data(&self) -> &[U256] {
+        &self.stack
+    }
+
     /// Get the current stack length.
     #[inline]
     pub fn len(&self) -> usize {
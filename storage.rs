@@ -0,0 +1,297 @@
+//! Axis VM accounts and contract storage
+
+use std::collections::{HashMap, HashSet};
+
+use ethereum_types::{H160, U256};
+use super::world::Account;
+
+/// Persistent storage of accounts and of their storage slots. Writes made
+/// with `set`, `clear` and `set_account` stay pending until `commit` makes
+/// them durable together, or `revert` drops them.
+pub trait StorageBackend {
+    /// Current value of `key` in the storage of `address`, including
+    /// pending writes. Unset slots read as zero.
+    fn get(&self, address: H160, key: U256) -> U256;
+
+    /// Value of `key` as of the last commit, ignoring pending writes.
+    fn committed(&self, address: H160, key: U256) -> U256;
+
+    /// Write a pending value of `key` in the storage of `address`.
+    fn set(&mut self, address: H160, key: U256, value: U256);
+
+    /// Zero every slot of `address`, pending until the next commit.
+    fn clear(&mut self, address: H160);
+
+    /// Every non-zero slot of `address`, including pending writes
+    fn slots(&self, address: H160) -> Vec<(U256, U256)>;
+
+    /// Every account as of the last commit
+    fn accounts(&self) -> Vec<(H160, Account)>;
+
+    /// Write a pending account at `address`, or delete it for `None`.
+    fn set_account(&mut self, address: H160, account: Option<Account>);
+
+    /// Make every pending write, of slots and accounts, durable at once.
+    fn commit(&mut self);
+
+    /// Drop every pending write.
+    fn revert(&mut self);
+}
+
+/// Storage kept in memory, for tests and ephemeral chains.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    committed: HashMap<(H160, U256), U256>,
+    pending: HashMap<(H160, U256), U256>,
+    cleared: HashSet<H160>,
+    accounts: HashMap<H160, Account>,
+    pending_accounts: HashMap<H160, Option<Account>>,
+}
+
+impl StorageBackend for MemoryStorage {
+    fn get(&self, address: H160, key: U256) -> U256 {
+        match self.pending.get(&(address, key)) {
+            Some(value) => *value,
+            None if self.cleared.contains(&address) => U256::zero(),
+            None => self.committed(address, key),
+        }
+    }
+
+    fn committed(&self, address: H160, key: U256) -> U256 {
+        self.committed.get(&(address, key)).cloned().unwrap_or_default()
+    }
+
+    fn set(&mut self, address: H160, key: U256, value: U256) {
+        self.pending.insert((address, key), value);
+    }
+
+    fn clear(&mut self, address: H160) {
+        self.pending.retain(|&(owner, _), _| owner != address);
+        self.cleared.insert(address);
+    }
+
+    fn slots(&self, address: H160) -> Vec<(U256, U256)> {
+        let mut slots = HashMap::new();
+        if !self.cleared.contains(&address) {
+            for (&(owner, key), &value) in &self.committed {
+                if owner == address {
+                    slots.insert(key, value);
+                }
+            }
+        }
+        for (&(owner, key), &value) in &self.pending {
+            if owner == address {
+                slots.insert(key, value);
+            }
+        }
+        slots.into_iter().filter(|&(_, value)| !value.is_zero()).collect()
+    }
+
+    fn accounts(&self) -> Vec<(H160, Account)> {
+        self.accounts.iter().map(|(&address, account)| (address, account.clone())).collect()
+    }
+
+    fn set_account(&mut self, address: H160, account: Option<Account>) {
+        self.pending_accounts.insert(address, account);
+    }
+
+    fn commit(&mut self) {
+        for (address, account) in self.pending_accounts.drain() {
+            match account {
+                Some(account) => self.accounts.insert(address, account),
+                None => self.accounts.remove(&address),
+            };
+        }
+
+        let cleared = &self.cleared;
+        self.committed.retain(|&(owner, _), _| !cleared.contains(&owner));
+        self.cleared.clear();
+
+        for (slot, value) in self.pending.drain() {
+            if value.is_zero() {
+                self.committed.remove(&slot);
+            } else {
+                self.committed.insert(slot, value);
+            }
+        }
+    }
+
+    fn revert(&mut self) {
+        self.pending.clear();
+        self.cleared.clear();
+        self.pending_accounts.clear();
+    }
+}
+
+#[cfg(feature = "sled")]
+pub use self::sled_backend::SledStorage;
+
+#[cfg(feature = "sled")]
+mod sled_backend {
+    extern crate rlp;
+    extern crate sled;
+
+    use std::collections::{HashMap, HashSet};
+    use std::path::Path;
+
+    use ethereum_types::{H160, U256};
+    use self::rlp::{Rlp, RlpStream};
+    use super::super::world::Account;
+    use super::StorageBackend;
+
+    /// Length of the database key of a storage slot
+    const SLOT_KEY_LEN: usize = 52;
+
+    /// Accounts and storage persisted in a sled database, so contract state
+    /// survives restarts. Accounts are keyed by their 20-byte address and
+    /// RLP-encoded; slots are keyed by `address ++ key`, and zero values are
+    /// not stored.
+    pub struct SledStorage {
+        db: sled::Db,
+        pending: HashMap<(H160, U256), U256>,
+        cleared: HashSet<H160>,
+        pending_accounts: HashMap<H160, Option<Account>>,
+    }
+
+    impl SledStorage {
+        /// Open or create the database at `path`.
+        pub fn open<P: AsRef<Path>>(path: P) -> sled::Result<SledStorage> {
+            Ok(SledStorage {
+                db: sled::open(path)?,
+                pending: HashMap::new(),
+                cleared: HashSet::new(),
+                pending_accounts: HashMap::new(),
+            })
+        }
+
+        fn db_key(address: H160, key: U256) -> [u8; SLOT_KEY_LEN] {
+            let mut db_key = [0u8; SLOT_KEY_LEN];
+            db_key[..20].copy_from_slice(address.as_bytes());
+            key.to_big_endian(&mut db_key[20..]);
+            db_key
+        }
+    }
+
+    /// `rlp([nonce, balance, storage_root, code_hash, code])`
+    fn encode_account(account: &Account) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(5);
+        stream.append(&account.nonce);
+        stream.append(&account.balance);
+        stream.append(&account.storage_root);
+        stream.append(&account.code_hash);
+        stream.append(&account.code);
+        stream.out().to_vec()
+    }
+
+    fn decode_account(bytes: &[u8]) -> Account {
+        let rlp = Rlp::new(bytes);
+        let field = "sled storage: corrupt account";
+        Account {
+            nonce: rlp.val_at(0).expect(field),
+            balance: rlp.val_at(1).expect(field),
+            storage_root: rlp.val_at(2).expect(field),
+            code_hash: rlp.val_at(3).expect(field),
+            code: rlp.val_at(4).expect(field),
+        }
+    }
+
+    impl StorageBackend for SledStorage {
+        fn get(&self, address: H160, key: U256) -> U256 {
+            match self.pending.get(&(address, key)) {
+                Some(value) => *value,
+                None if self.cleared.contains(&address) => U256::zero(),
+                None => self.committed(address, key),
+            }
+        }
+
+        fn committed(&self, address: H160, key: U256) -> U256 {
+            let value = self
+                .db
+                .get(&SledStorage::db_key(address, key)[..])
+                .expect("sled storage: read failed");
+            match value {
+                Some(bytes) => U256::from(&bytes[..]),
+                None => U256::zero(),
+            }
+        }
+
+        fn set(&mut self, address: H160, key: U256, value: U256) {
+            self.pending.insert((address, key), value);
+        }
+
+        fn clear(&mut self, address: H160) {
+            self.pending.retain(|&(owner, _), _| owner != address);
+            self.cleared.insert(address);
+        }
+
+        fn slots(&self, address: H160) -> Vec<(U256, U256)> {
+            let mut slots = HashMap::new();
+            if !self.cleared.contains(&address) {
+                for entry in self.db.scan_prefix(address.as_bytes()) {
+                    let (db_key, value) = entry.expect("sled storage: read failed");
+                    if db_key.len() == SLOT_KEY_LEN {
+                        slots.insert(U256::from(&db_key[20..]), U256::from(&value[..]));
+                    }
+                }
+            }
+            for (&(owner, key), &value) in &self.pending {
+                if owner == address {
+                    slots.insert(key, value);
+                }
+            }
+            slots.into_iter().filter(|&(_, value)| !value.is_zero()).collect()
+        }
+
+        /// Scans the whole database; it is meant to run once, when the
+        /// state is loaded.
+        fn accounts(&self) -> Vec<(H160, Account)> {
+            self.db
+                .iter()
+                .map(|entry| entry.expect("sled storage: read failed"))
+                .filter(|&(ref db_key, _)| db_key.len() == 20)
+                .map(|(db_key, bytes)| (H160::from_slice(&db_key), decode_account(&bytes)))
+                .collect()
+        }
+
+        fn set_account(&mut self, address: H160, account: Option<Account>) {
+            self.pending_accounts.insert(address, account);
+        }
+
+        fn commit(&mut self) {
+            let mut batch = sled::Batch::default();
+            for address in self.cleared.drain() {
+                for entry in self.db.scan_prefix(address.as_bytes()) {
+                    let (db_key, _) = entry.expect("sled storage: read failed");
+                    // The prefix also matches the account itself
+                    if db_key.len() == SLOT_KEY_LEN {
+                        batch.remove(db_key);
+                    }
+                }
+            }
+            for (address, account) in self.pending_accounts.drain() {
+                match account {
+                    Some(account) => batch.insert(address.as_bytes(), encode_account(&account)),
+                    None => batch.remove(address.as_bytes()),
+                }
+            }
+            for ((address, key), value) in self.pending.drain() {
+                let db_key = SledStorage::db_key(address, key);
+                if value.is_zero() {
+                    batch.remove(&db_key[..]);
+                } else {
+                    let mut bytes = [0u8; 32];
+                    value.to_big_endian(&mut bytes);
+                    batch.insert(&db_key[..], &bytes[..]);
+                }
+            }
+            self.db.apply_batch(batch).expect("sled storage: commit failed");
+            self.db.flush().expect("sled storage: flush failed");
+        }
+
+        fn revert(&mut self) {
+            self.pending.clear();
+            self.cleared.clear();
+            self.pending_accounts.clear();
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! EIP-3155 JSON tracer
+//!
+//! Writes one JSON line per executed instruction and a summary line at the
+//! end, in the format of `evm --json`, so traces can be diffed against other
+//! clients.
+
+use std::io::{self, Write};
+
+use super::inspector::{CallInputs, CreateInputs, FrameResult, Inspector};
+use super::opcode;
+use super::vm::{ExecutionOutcome, Halt, AXISVM};
+use super::world::{StateError, WorldState};
+
+/// An instruction whose line is written once its gas cost is known
+struct PendingStep {
+    pc: usize,
+    opcode: u8,
+    gas: usize,
+    mem_size: usize,
+    stack: String,
+    depth: usize,
+    refund: i64,
+}
+
+/// Inspector writing an EIP-3155 trace to `out`
+pub struct JsonTracer<W: Write> {
+    out: W,
+    pending: Option<PendingStep>,
+    /// Refund counters of the calling frames; a frame's refund only joins
+    /// its caller's when it succeeds
+    parent_refunds: Vec<i64>,
+    /// First write error; hooks cannot return it, so `summary` does
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonTracer<W> {
+    pub fn new(out: W) -> JsonTracer<W> {
+        JsonTracer {
+            out,
+            pending: None,
+            parent_refunds: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Write the summary line of the finished transaction, or the first
+    /// error met while writing the trace.
+    pub fn summary(&mut self, outcome: &ExecutionOutcome) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        let output: String = outcome.returns().iter().map(|b| format!("{:02x}", b)).collect();
+        write!(
+            self.out,
+            "{{\"output\":\"{}\",\"gasUsed\":\"0x{:x}\",\"pass\":{}",
+            output,
+            outcome.gas_used(),
+            outcome.is_success()
+        )?;
+        if let Some(error) = outcome_error(outcome) {
+            write!(self.out, ",\"error\":\"{}\"", error)?;
+        }
+        writeln!(self.out, "}}")?;
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Write the line of the pending instruction, which left `gas_left`.
+    fn flush_step(&mut self, gas_left: usize, halt: Option<Halt>) {
+        let step = match self.pending.take() {
+            Some(step) => step,
+            None => return,
+        };
+        if self.error.is_some() {
+            return;
+        }
+
+        let op_name = match opcode::mnemonic(step.opcode) {
+            Some(mnemonic) => mnemonic.to_string(),
+            None => format!("opcode 0x{:x} not defined", step.opcode),
+        };
+        let mut line = format!(
+            "{{\"pc\":{},\"op\":{},\"gas\":\"0x{:x}\",\"gasCost\":\"0x{:x}\",\"memSize\":{},\
+             \"stack\":[{}],\"depth\":{},\"refund\":{},\"opName\":\"{}\"",
+            step.pc,
+            step.opcode,
+            step.gas,
+            step.gas.saturating_sub(gas_left),
+            step.mem_size,
+            step.stack,
+            step.depth,
+            step.refund,
+            op_name
+        );
+        if let Some(error) = halt.and_then(halt_error) {
+            line.push_str(&format!(",\"error\":\"{}\"", error));
+        }
+        line.push('}');
+
+        if let Err(err) = writeln!(self.out, "{}", line) {
+            self.error = Some(err);
+        }
+    }
+}
+
+impl<W: Write> Inspector for JsonTracer<W> {
+    fn step(&mut self, vm: &AXISVM, _state: &WorldState) {
+        let stack: Vec<String> =
+            vm.stack().data().iter().map(|v| format!("\"0x{:x}\"", v)).collect();
+        let parent_refund: i64 = self.parent_refunds.iter().sum();
+        self.pending = Some(PendingStep {
+            pc: vm.pc(),
+            opcode: vm.opcode().unwrap_or(0),
+            gas: vm.gas(),
+            mem_size: vm.memory().len(),
+            stack: stack.join(","),
+            depth: vm.depth() + 1,
+            refund: parent_refund + vm.refund(),
+        });
+    }
+
+    fn step_end(&mut self, vm: &AXISVM, _state: &WorldState, result: &Result<(), Halt>) {
+        self.flush_step(vm.gas(), result.err());
+    }
+
+    /// The CALL line is written before the lines of the callee
+    fn call(&mut self, caller: &AXISVM, _inputs: &CallInputs) {
+        self.flush_step(caller.gas(), None);
+        self.parent_refunds.push(caller.refund());
+    }
+
+    fn call_end(&mut self, _inputs: &CallInputs, _result: &FrameResult) {
+        self.parent_refunds.pop();
+    }
+
+    fn create(&mut self, creator: &AXISVM, _inputs: &CreateInputs) {
+        self.flush_step(creator.gas(), None);
+        self.parent_refunds.push(creator.refund());
+    }
+
+    fn create_end(&mut self, _inputs: &CreateInputs, _result: &FrameResult) {
+        self.parent_refunds.pop();
+    }
+}
+
+/// Error reported for an instruction that halted its frame exceptionally
+fn halt_error(halt: Halt) -> Option<String> {
+    let error = match halt {
+        Halt::Stop | Halt::Return | Halt::Revert => return None,
+        Halt::OutOfGas => "out of gas".to_string(),
+        Halt::InvalidOpcode(opcode) => format!("invalid opcode: 0x{:x}", opcode),
+        Halt::StackUnderflow => "stack underflow".to_string(),
+        Halt::StackOverflow => "stack limit reached 1024".to_string(),
+        Halt::InvalidJump => "invalid jump destination".to_string(),
+        Halt::StaticViolation => "write protection".to_string(),
+        Halt::ReturnDataOutOfBounds => "return data out of bounds".to_string(),
+        Halt::PrecompileFailure => "precompile failed".to_string(),
+    };
+    Some(error)
+}
+
+/// Error reported in the summary of a failed transaction
+fn outcome_error(outcome: &ExecutionOutcome) -> Option<String> {
+    let halt = match *outcome {
+        ExecutionOutcome::Success { .. } => return None,
+        ExecutionOutcome::Revert { .. } => return Some("execution reverted".to_string()),
+        ExecutionOutcome::OutOfGas { .. } => Halt::OutOfGas,
+        ExecutionOutcome::InvalidOpcode { opcode, .. } => Halt::InvalidOpcode(opcode),
+        ExecutionOutcome::StackUnderflow { .. } => Halt::StackUnderflow,
+        ExecutionOutcome::StackOverflow { .. } => Halt::StackOverflow,
+        ExecutionOutcome::InvalidJump { .. } => Halt::InvalidJump,
+        ExecutionOutcome::StaticViolation { .. } => Halt::StaticViolation,
+        ExecutionOutcome::ReturnDataOutOfBounds { .. } => Halt::ReturnDataOutOfBounds,
+        ExecutionOutcome::PrecompileFailure { .. } => Halt::PrecompileFailure,
+        ExecutionOutcome::Rejected { error: StateError::InsufficientBalance } => {
+            return Some("insufficient balance for transfer".to_string())
+        }
+        ExecutionOutcome::Rejected { error: StateError::NonceOverflow } => {
+            return Some("nonce has max value".to_string())
+        }
+    };
+    halt_error(halt)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_types::{H160, U256};
+    use super::*;
+    use super::super::block::BlockEnv;
+    use super::super::spec::SpecId;
+    use super::super::tx::TxEnv;
+    use super::super::vm::Environment;
+
+    /// A CALL into a frame that reads its input, then an invalid opcode
+    #[test]
+    fn nested_call_and_failure() {
+        let child = H160::from_low_u64_be(0x2000);
+        let mut state = WorldState::default();
+        // PUSH1 0 CALLDATALOAD STOP
+        state.set_code(child, vec![0x60, 0x00, 0x35, 0x00]);
+
+        // MSTORE 0x2a at 0, CALL the child with that word as input and
+        // 0xffff gas, POP, INVALID
+        let code = vec![
+            0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x00, 0x60, 0x00, 0x60, 0x20, 0x60, 0x00, 0x60,
+            0x00, 0x61, 0x20, 0x00, 0x61, 0xff, 0xff, 0xf1, 0x50, 0xfe,
+        ];
+        let origin = H160::from_low_u64_be(0x01);
+        let mut env = Environment::new(H160::from_low_u64_be(0x1000), origin, U256::zero());
+        env.set_code(code);
+        let tx = TxEnv { origin, gas_limit: 100_000, ..TxEnv::default() };
+        let mut vm = AXISVM::new(env, tx, BlockEnv::default());
+        vm.set_spec(SpecId::Istanbul);
+
+        let mut tracer = JsonTracer::new(Vec::new());
+        let outcome = vm.inspect(&mut state, &mut tracer);
+        tracer.summary(&outcome).unwrap();
+
+        let trace = String::from_utf8(tracer.into_inner()).unwrap();
+        let expected = [
+            "{\"pc\":0,\"op\":96,\"gas\":\"0x186a0\",\"gasCost\":\"0x3\",\"memSize\":0,\
+             \"stack\":[],\"depth\":1,\"refund\":0,\"opName\":\"PUSH1\"}",
+            "{\"pc\":2,\"op\":96,\"gas\":\"0x1869d\",\"gasCost\":\"0x3\",\"memSize\":0,\
+             \"stack\":[\"0x2a\"],\"depth\":1,\"refund\":0,\"opName\":\"PUSH1\"}",
+            "{\"pc\":4,\"op\":82,\"gas\":\"0x1869a\",\"gasCost\":\"0x6\",\"memSize\":0,\
+             \"stack\":[\"0x2a\",\"0x0\"],\"depth\":1,\"refund\":0,\"opName\":\"MSTORE\"}",
+            "{\"pc\":5,\"op\":96,\"gas\":\"0x18694\",\"gasCost\":\"0x3\",\"memSize\":32,\
+             \"stack\":[],\"depth\":1,\"refund\":0,\"opName\":\"PUSH1\"}",
+            "{\"pc\":7,\"op\":96,\"gas\":\"0x18691\",\"gasCost\":\"0x3\",\"memSize\":32,\
+             \"stack\":[\"0x0\"],\"depth\":1,\"refund\":0,\"opName\":\"PUSH1\"}",
+            "{\"pc\":9,\"op\":96,\"gas\":\"0x1868e\",\"gasCost\":\"0x3\",\"memSize\":32,\
+             \"stack\":[\"0x0\",\"0x0\"],\"depth\":1,\"refund\":0,\"opName\":\"PUSH1\"}",
+            "{\"pc\":11,\"op\":96,\"gas\":\"0x1868b\",\"gasCost\":\"0x3\",\"memSize\":32,\
+             \"stack\":[\"0x0\",\"0x0\",\"0x20\"],\"depth\":1,\"refund\":0,\
+             \"opName\":\"PUSH1\"}",
+            "{\"pc\":13,\"op\":96,\"gas\":\"0x18688\",\"gasCost\":\"0x3\",\"memSize\":32,\
+             \"stack\":[\"0x0\",\"0x0\",\"0x20\",\"0x0\"],\"depth\":1,\"refund\":0,\
+             \"opName\":\"PUSH1\"}",
+            "{\"pc\":15,\"op\":97,\"gas\":\"0x18685\",\"gasCost\":\"0x3\",\"memSize\":32,\
+             \"stack\":[\"0x0\",\"0x0\",\"0x20\",\"0x0\",\"0x0\"],\"depth\":1,\"refund\":0,\
+             \"opName\":\"PUSH2\"}",
+            "{\"pc\":18,\"op\":97,\"gas\":\"0x18682\",\"gasCost\":\"0x3\",\"memSize\":32,\
+             \"stack\":[\"0x0\",\"0x0\",\"0x20\",\"0x0\",\"0x0\",\"0x2000\"],\
+             \"depth\":1,\"refund\":0,\"opName\":\"PUSH2\"}",
+            "{\"pc\":21,\"op\":241,\"gas\":\"0x1867f\",\"gasCost\":\"0x102bb\",\
+             \"memSize\":32,\
+             \"stack\":[\"0x0\",\"0x0\",\"0x20\",\"0x0\",\"0x0\",\"0x2000\",\"0xffff\"],\
+             \"depth\":1,\"refund\":0,\"opName\":\"CALL\"}",
+            "{\"pc\":0,\"op\":96,\"gas\":\"0xffff\",\"gasCost\":\"0x3\",\"memSize\":0,\
+             \"stack\":[],\"depth\":2,\"refund\":0,\"opName\":\"PUSH1\"}",
+            "{\"pc\":2,\"op\":53,\"gas\":\"0xfffc\",\"gasCost\":\"0x3\",\"memSize\":0,\
+             \"stack\":[\"0x0\"],\"depth\":2,\"refund\":0,\"opName\":\"CALLDATALOAD\"}",
+            "{\"pc\":3,\"op\":0,\"gas\":\"0xfff9\",\"gasCost\":\"0x0\",\"memSize\":0,\
+             \"stack\":[\"0x2a\"],\"depth\":2,\"refund\":0,\"opName\":\"STOP\"}",
+            "{\"pc\":22,\"op\":80,\"gas\":\"0x183bd\",\"gasCost\":\"0x2\",\"memSize\":32,\
+             \"stack\":[\"0x1\"],\"depth\":1,\"refund\":0,\"opName\":\"POP\"}",
+            "{\"pc\":23,\"op\":254,\"gas\":\"0x183bb\",\"gasCost\":\"0x0\",\"memSize\":32,\
+             \"stack\":[],\"depth\":1,\"refund\":0,\"opName\":\"INVALID\",\
+             \"error\":\"invalid opcode: 0xfe\"}",
+            "{\"output\":\"\",\"gasUsed\":\"0x186a0\",\"pass\":false,\
+             \"error\":\"invalid opcode: 0xfe\"}",
+        ];
+        assert_eq!(trace.lines().collect::<Vec<_>>(), expected.to_vec());
+    }
+}
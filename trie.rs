@@ -0,0 +1,196 @@
+//! Axis Merkle Patricia trie roots
+//!
+//! Computes the root hash of a trie from the full set of its entries, as
+//! the yellow paper defines it, without storing any nodes. Used for the
+//! storage root of accounts.
+
+extern crate rlp;
+
+use ethereum_types::{H256, U256};
+use self::rlp::RlpStream;
+use super::hash;
+
+/// Root of the trie holding `entries`, as `(key, value)` pairs with
+/// distinct keys
+pub fn trie_root(entries: Vec<(Vec<u8>, Vec<u8>)>) -> H256 {
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = entries
+        .into_iter()
+        .map(|(key, value)| (nibbles(&key), value))
+        .collect();
+    entries.sort();
+
+    let mut stream = RlpStream::new();
+    encode_node(&entries, 0, &mut stream);
+    hash::keccak256(&stream.out())
+}
+
+/// Storage root of an account with the given slots: the root of the trie
+/// of `keccak(key) -> rlp(value)` over the non-zero slots
+pub fn storage_root(slots: &[(U256, U256)]) -> H256 {
+    let entries = slots
+        .iter()
+        .filter(|&&(_, value)| !value.is_zero())
+        .map(|&(key, value)| {
+            let mut word = [0u8; 32];
+            key.to_big_endian(&mut word);
+            (hash::keccak256(&word).as_bytes().to_vec(), rlp::encode(&value).to_vec())
+        })
+        .collect();
+    trie_root(entries)
+}
+
+/// Nibbles of `bytes`, high nibble first
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(2 * bytes.len());
+    for &byte in bytes {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    nibbles
+}
+
+/// Hex-prefix encoding of a path of nibbles, flagged as the path of a leaf
+/// or of an extension
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 0x20 } else { 0x00 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push(flag | 0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        encoded.push(pair[0] << 4 | pair[1]);
+    }
+    encoded
+}
+
+/// Append the node holding the sorted `entries`, whose keys share their
+/// first `prefix` nibbles, to `stream`.
+fn encode_node(entries: &[(Vec<u8>, Vec<u8>)], prefix: usize, stream: &mut RlpStream) {
+    let (first_key, first_value) = match entries.first() {
+        Some(&(ref key, ref value)) => (key, value),
+        None => {
+            stream.append_empty_data();
+            return;
+        }
+    };
+    if entries.len() == 1 {
+        stream.begin_list(2);
+        stream.append(&hex_prefix(&first_key[prefix..], true));
+        stream.append(first_value);
+        return;
+    }
+
+    // The keys are sorted, so the first and the last share the prefix
+    // common to all of them
+    let last_key = &entries[entries.len() - 1].0;
+    let shared = first_key[prefix..]
+        .iter()
+        .zip(&last_key[prefix..])
+        .take_while(|&(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        stream.begin_list(2);
+        stream.append(&hex_prefix(&first_key[prefix..prefix + shared], false));
+        append_child(entries, prefix + shared, stream);
+        return;
+    }
+
+    // A key ending here sorts first and is the value of the branch
+    let (value, mut rest) = if first_key.len() == prefix {
+        (Some(first_value), &entries[1..])
+    } else {
+        (None, entries)
+    };
+    stream.begin_list(17);
+    for nibble in 0..16 {
+        let count = rest.iter().take_while(|entry| entry.0[prefix] == nibble).count();
+        if count == 0 {
+            stream.append_empty_data();
+        } else {
+            append_child(&rest[..count], prefix + 1, stream);
+        }
+        rest = &rest[count..];
+    }
+    match value {
+        Some(value) => stream.append(value),
+        None => stream.append_empty_data(),
+    };
+}
+
+/// Append the node holding `entries` to `stream`: inline if its encoding
+/// is shorter than a hash, by its hash otherwise.
+fn append_child(entries: &[(Vec<u8>, Vec<u8>)], prefix: usize, stream: &mut RlpStream) {
+    let mut child = RlpStream::new();
+    encode_node(entries, prefix, &mut child);
+    let encoded = child.out();
+    if encoded.len() < 32 {
+        stream.append_raw(&encoded, 1);
+    } else {
+        stream.append(&hash::keccak256(&encoded));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::world::EMPTY_STORAGE_ROOT;
+
+    fn entries(pairs: &[(&str, &str)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        pairs
+            .iter()
+            .map(|&(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+            .collect()
+    }
+
+    fn h256(hex: &str) -> H256 {
+        let bytes: Vec<u8> = (0..64)
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        H256::from_slice(&bytes)
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(trie_root(Vec::new()), EMPTY_STORAGE_ROOT);
+        assert_eq!(storage_root(&[]), EMPTY_STORAGE_ROOT);
+        assert_eq!(storage_root(&[(U256::one(), U256::zero())]), EMPTY_STORAGE_ROOT);
+    }
+
+    /// The "dogs" and "puppy" cases of the Ethereum trie tests
+    #[test]
+    fn known_roots() {
+        let dogs = entries(&[("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")]);
+        assert_eq!(
+            trie_root(dogs),
+            h256("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+        );
+        let puppy =
+            entries(&[("do", "verb"), ("horse", "stallion"), ("doge", "coin"), ("dog", "puppy")]);
+        assert_eq!(
+            trie_root(puppy),
+            h256("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+        );
+    }
+
+    #[test]
+    fn storage_roots() {
+        assert_eq!(
+            storage_root(&[(U256::zero(), U256::one())]),
+            h256("821e2556a290c86405f8160a2d662042a431ba456b9db265c79bb837c04be5f0")
+        );
+        let slots = [
+            (U256::zero(), U256::from(0x2a)),
+            (U256::one(), U256::from(0x1234_5678_90ab_cdefu64)),
+            (U256::from(0x20), U256::one() << 255),
+        ];
+        assert_eq!(
+            storage_root(&slots),
+            h256("9ceca690a8294ab5cd2418d2bcddeed7f590e692f1c03071bf96b6ccde26fc06")
+        );
+    }
+}
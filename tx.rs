@@ -0,0 +1,54 @@
+//! Axis transaction context
+
+use ethereum_types::{H160, H256, U256};
+use super::world::{StateError, WorldState};
+
+/// An entry of an EIP-2930 access list: an account and the storage slots
+/// of it the transaction declares it will access
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessListItem {
+    pub address: H160,
+    pub storage_keys: Vec<U256>,
+}
+
+/// Transaction-wide context shared by every call frame
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxEnv {
+    /// Externally owned account that signed the transaction
+    pub origin: H160,
+    /// Price paid per unit of gas
+    pub gas_price: U256,
+    /// Gas available to the transaction
+    pub gas_limit: usize,
+    /// Chain the transaction was signed for (EIP-155)
+    pub chain_id: u64,
+    /// Accounts and slots warm from the start of execution (EIP-2930)
+    pub access_list: Vec<AccessListItem>,
+    /// Versioned hashes of the blobs carried by the transaction (EIP-4844)
+    pub blob_hashes: Vec<H256>,
+}
+
+impl TxEnv {
+    /// Fee for `gas` units at the transaction's gas price
+    pub fn fee(&self, gas: usize) -> U256 {
+        let (fee, overflow) = U256::from(gas).overflowing_mul(self.gas_price);
+        if overflow {
+            U256::max_value()
+        } else {
+            fee
+        }
+    }
+}
+
+/// Buy the transaction's gas up front by debiting `gas_limit * gas_price`
+/// from the origin. Done before execution, and kept even if the
+/// transaction fails.
+pub fn charge_upfront(state: &mut WorldState, tx: &TxEnv) -> Result<(), StateError> {
+    state.sub_balance(tx.origin, tx.fee(tx.gas_limit))
+}
+
+/// Return the fee of the gas left unused to the origin after execution.
+pub fn refund_unused(state: &mut WorldState, tx: &TxEnv, gas_used: usize) {
+    let unused = tx.gas_limit.saturating_sub(gas_used);
+    state.add_balance(tx.origin, tx.fee(unused));
+}
@@ -6,33 +6,45 @@
 
 
 
-use util::not_implement_panic;
+use std::rc::Rc;
+
 use super::util;
-use super::state;
+use super::log::{self, Log};
+use super::world::{self, StateError, WorldState};
+use super::stack::Stack;
+use super::arith;
+use super::hash;
+use super::memory::{self, Memory};
+use super::analysis::{self, JumpTable};
+use super::block::BlockEnv;
+use super::tx::TxEnv;
+use super::spec::{GasSchedule, SpecId};
+use super::precompile::{Precompile, Precompiles};
+use super::inspector::{CallInputs, CreateInputs, FrameResult, Inspector, NoopInspector};
+use super::errors::OnChainError;
 extern crate ethereum_types;
-use ethereum_types::{H160, U256};
+use ethereum_types::{Bloom, H160, H256, U256};
+
 
 
+/// Maximum depth of nested message calls
+const CALL_DEPTH_LIMIT: usize = 1024;
 
 // Environment specifications 
 pub struct Environment {
- 
-    gas_cost: usize, // gas
-    value: usize,     
     code: Vec<u8>,    
     input: Vec<u8>,  
     code_supervisor: H160, 
     sender: H160,     
-  
+    call_value: U256, // value transferred with the call
 }
 
 impl Environment {
-    pub fn new(code_supervisor: H160, sender: H160, gas_cost: usize, value: usize) -> Self {
+    pub fn new(code_supervisor: H160, sender: H160, call_value: U256) -> Self {
         return Self {
             code_supervisor,
             sender,
-            gas_cost,
-            value,
+            call_value,
             code: Default::default(),
             input: Default::default(),
         };
@@ -53,52 +65,303 @@ impl Environment {
   
 }
 
+/// Reason the interpreter loop stopped executing the current code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    Stop,
+    Return,
+    Revert,
+    OutOfGas,
+    InvalidOpcode(u8),
+    StackUnderflow,
+    StackOverflow,
+    InvalidJump,
+    /// State modification attempted inside STATICCALL
+    StaticViolation,
+    /// RETURNDATACOPY read past the end of the return data (EIP-211)
+    ReturnDataOutOfBounds,
+    /// A precompiled contract rejected its input
+    PrecompileFailure,
+}
+
+impl From<OnChainError> for Halt {
+    fn from(err: OnChainError) -> Halt {
+        match err {
+            OnChainError::StackUnderflow => Halt::StackUnderflow,
+            OnChainError::StackOverflow => Halt::StackOverflow,
+        }
+    }
+}
+
+/// Flavour of a message call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+}
+
+/// Flavour of contract creation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreateKind {
+    Create,
+    Create2,
+}
+
+/// Result of a single opcode handler; `Err` stops the interpreter loop
+type OpResult = Result<(), Halt>;
+
+/// Convert a stack word used as a memory offset or length. Values this large
+/// could never be paid for, so they halt as out of gas.
+fn as_usize(value: U256) -> Result<usize, Halt> {
+    if value > U256::from(u32::max_value()) {
+        Err(Halt::OutOfGas)
+    } else {
+        Ok(value.low_u32() as usize)
+    }
+}
+
+/// Result of a transaction executed by the AXIS VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    Success { gas_used: usize, returns: Vec<u8>, logs: Vec<Log> },
+    Revert { gas_used: usize, returns: Vec<u8> },
+    OutOfGas { gas_used: usize },
+    InvalidOpcode { opcode: u8, gas_used: usize },
+    StackUnderflow { gas_used: usize },
+    StackOverflow { gas_used: usize },
+    InvalidJump { gas_used: usize },
+    StaticViolation { gas_used: usize },
+    ReturnDataOutOfBounds { gas_used: usize },
+    PrecompileFailure { gas_used: usize },
+    /// The transaction could not start: the sender cannot cover the call
+    /// value or the origin's nonce is exhausted. Nothing was executed.
+    Rejected { error: StateError },
+}
+
+impl ExecutionOutcome {
+    pub fn is_success(&self) -> bool {
+        match *self {
+            ExecutionOutcome::Success { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Gas charged to the transaction, whatever the outcome
+    pub fn gas_used(&self) -> usize {
+        match *self {
+            ExecutionOutcome::Success { gas_used, .. }
+            | ExecutionOutcome::Revert { gas_used, .. }
+            | ExecutionOutcome::OutOfGas { gas_used }
+            | ExecutionOutcome::InvalidOpcode { gas_used, .. }
+            | ExecutionOutcome::StackUnderflow { gas_used }
+            | ExecutionOutcome::StackOverflow { gas_used }
+            | ExecutionOutcome::InvalidJump { gas_used }
+            | ExecutionOutcome::StaticViolation { gas_used }
+            | ExecutionOutcome::ReturnDataOutOfBounds { gas_used }
+            | ExecutionOutcome::PrecompileFailure { gas_used } => gas_used,
+            ExecutionOutcome::Rejected { .. } => 0,
+        }
+    }
+
+    /// Data returned by RETURN or REVERT, empty for exceptional halts
+    pub fn returns(&self) -> &[u8] {
+        match *self {
+            ExecutionOutcome::Success { ref returns, .. }
+            | ExecutionOutcome::Revert { ref returns, .. } => returns,
+            _ => &[],
+        }
+    }
+
+    /// Logs emitted by a successful transaction
+    pub fn logs(&self) -> &[Log] {
+        match *self {
+            ExecutionOutcome::Success { ref logs, .. } => logs,
+            _ => &[],
+        }
+    }
+
+    /// Receipt bloom of the logs emitted by the transaction
+    pub fn logs_bloom(&self) -> Bloom {
+        log::logs_bloom(self.logs())
+    }
+}
+
+/// Transaction-wide settings shared by every frame of a transaction
+struct Shared {
+    tx: Rc<TxEnv>,
+    block: Rc<BlockEnv>,
+    spec: SpecId,
+    schedule: Rc<GasSchedule>,
+    precompiles: Rc<Precompiles>,
+}
+
+/// A call frame. Every message call runs in a frame of its own, with its
+/// own pc, stack, memory and `Environment`; nested frames live on the call
+/// stack of the calling frame's CALL handler.
 pub struct AXISVM {
     env: Environment, 
+    tx: Rc<TxEnv>,    // transaction the frame belongs to
+    spec: SpecId,     // hard fork deciding the valid opcodes
+    schedule: Rc<GasSchedule>, // gas costs of the instructions
+    precompiles: Rc<Precompiles>, // native contracts run instead of code
+    block: Rc<BlockEnv>, // block the transaction executes in
+    depth: usize,     // call depth, 0 for the transaction frame
+    is_static: bool,  // state changes are forbidden (STATICCALL)
     pc: usize,        // AXIS VM Program Counter
+    jumps: Rc<JumpTable>, // Valid JUMPDEST offsets of the running code
+    gas_limit: usize, // gas available when the transaction started
     gas: usize,       // gas
-    sp: usize,        
-    asm: Vec<String>, // Store the executed instruction Used for disassembly
-    stack: Vec<U256>, // Temporary stack area retained for the life cycle of a transaction
-    memory: Vec<u8>,  // Temporary memory area retained during the life cycle of a transaction
+    refund: i64,      // gas refund counter, applied when the transaction succeeds
+    stack: Stack,     // Temporary stack area retained for the life cycle of a transaction
+    memory: Memory,   // Temporary memory area retained during the life cycle of a transaction
     returns: Vec<u8>, // Action return value
+    return_data: Vec<u8>, // Output of the last completed sub-call
 }
 
 /// Opcode
 impl AXISVM {
-    pub fn new(env: Environment) -> Self {
-        let gas = env.value / env.gas_cost;
+    /// Transaction frame with the transaction's full gas limit. The gas
+    /// itself must already be paid for, see `tx::charge_upfront`.
+    pub fn new(env: Environment, tx: TxEnv, block: BlockEnv) -> Self {
+        let gas = tx.gas_limit;
+        let spec = SpecId::default();
+        let shared = Shared {
+            tx: Rc::new(tx),
+            block: Rc::new(block),
+            spec,
+            schedule: Rc::new(spec.schedule()),
+            precompiles: Rc::new(Precompiles::for_spec(spec)),
+        };
+        let jumps = Rc::new(JumpTable::analyze(&env.code));
+        Self::new_frame(env, shared, jumps, gas, 0, false)
+    }
 
+    /// Run under the opcode set, gas schedule and precompiles of `spec`.
+    /// Precompiles registered before are dropped.
+    pub fn set_spec(&mut self, spec: SpecId) {
+        self.spec = spec;
+        self.schedule = Rc::new(spec.schedule());
+        self.precompiles = Rc::new(Precompiles::for_spec(spec));
+    }
+
+    /// Install an Axis-specific precompile at `address`, replacing any
+    /// precompile there.
+    pub fn register_precompile<P: Precompile + 'static>(&mut self, address: H160, precompile: P) {
+        Rc::make_mut(&mut self.precompiles).register(address, precompile);
+    }
+
+    /// Charge gas according to `schedule` instead of the spec's own, e.g.
+    /// a revised Axis schedule loaded with `GasSchedule::from_json`.
+    pub fn set_gas_schedule(&mut self, schedule: GasSchedule) {
+        self.schedule = Rc::new(schedule);
+    }
+
+    /// Frame for a message call at `depth` with `gas` available, running
+    /// `env.code` whose jump destinations are `jumps`
+    fn new_frame(
+        env: Environment,
+        shared: Shared,
+        jumps: Rc<JumpTable>,
+        gas: usize,
+        depth: usize,
+        is_static: bool,
+    ) -> Self {
         Self {
             env,
+            tx: shared.tx,
+            block: shared.block,
+            spec: shared.spec,
+            schedule: shared.schedule,
+            precompiles: shared.precompiles,
+            depth,
+            is_static,
             pc: 0,
+            jumps,
+            gas_limit: gas,
             gas,
-            sp: 0,
+            refund: 0,
             stack: Default::default(),
             memory: Default::default(),
-            asm: Default::default(),
             returns: Default::default(),
+            return_data: Default::default(),
+        }
+    }
+
+    /// Frame for a message call made by this frame
+    fn child_frame(
+        &self,
+        env: Environment,
+        jumps: Rc<JumpTable>,
+        gas: usize,
+        is_static: bool,
+    ) -> Self {
+        let shared = Shared {
+            tx: self.tx.clone(),
+            block: self.block.clone(),
+            spec: self.spec,
+            schedule: self.schedule.clone(),
+            precompiles: self.precompiles.clone(),
+        };
+        Self::new_frame(env, shared, jumps, gas, self.depth + 1, is_static)
+    }
+
+    /// Inputs of the message call running in this frame
+    fn call_inputs(&self, kind: CallKind, code_address: H160) -> CallInputs {
+        CallInputs {
+            kind,
+            caller: self.env.sender,
+            code_address,
+            context_address: self.env.code_supervisor,
+            value: self.env.call_value,
+            input: &self.env.input,
+            gas: self.gas_limit,
+            depth: self.depth,
+            is_static: self.is_static,
+        }
+    }
+
+    /// Inputs of the contract creation running in this frame
+    fn create_inputs(&self, kind: CreateKind) -> CreateInputs {
+        CreateInputs {
+            kind,
+            creator: self.env.sender,
+            address: self.env.code_supervisor,
+            value: self.env.call_value,
+            init_code: &self.env.code,
+            gas: self.gas_limit,
+            depth: self.depth,
         }
     }
 
     /// push to the AXIS stack
-    fn push(&mut self, value: U256) {
-        self.stack.push(value);
-        self.sp += 1;
+    fn push(&mut self, value: U256) -> OpResult {
+        Ok(self.stack.push(value)?)
     }
 
     /// pop from the AXIS stack
-    fn pop(&mut self) -> U256 {
-        let value = self.stack.pop().unwrap();
-        self.sp -= 1;
-        return value;
+    fn pop(&mut self) -> Result<U256, Halt> {
+        Ok(self.stack.pop()?)
     }
 
     /// code execution
-    fn exec(&mut self, contract: &mut state::AccountState) -> bool {
+    fn exec<I: Inspector>(&mut self, state: &mut WorldState, inspector: &mut I) -> OpResult {
+        inspector.step(self, state);
+        let result = self.dispatch(state, inspector);
+        inspector.step_end(self, state, &result);
+        result
+    }
+
+    /// Run the instruction at pc
+    fn dispatch<I: Inspector>(&mut self, state: &mut WorldState, inspector: &mut I) -> OpResult {
         let opcode = self.env.code[self.pc];
         self.pc += 1;
 
+        if !self.spec.has_opcode(opcode) {
+            return Err(Halt::InvalidOpcode(opcode));
+        }
+
         // opcodes -- supporting the EVM opcs updatable based on new EVM opcodes and other virtual machines such as Tron
         match opcode {
             // 0x00
@@ -126,11 +389,14 @@ impl AXISVM {
             0x18 => self.op_xor(),
             0x19 => self.op_not(),
             0x1a => self.op_byte(),
+            0x1b => self.op_shl(),
+            0x1c => self.op_shr(),
+            0x1d => self.op_sar(),
             // 0x20
             0x20 => self.op_sha3(),
             // 0x30
             0x30 => self.op_address(),
-            0x31 => self.op_balance(),
+            0x31 => self.op_balance(state),
             0x32 => self.op_origin(),
             0x33 => self.op_caller(),
             0x34 => self.op_callvalue(),
@@ -140,11 +406,11 @@ impl AXISVM {
             0x38 => self.op_codesize(),
             0x39 => self.op_codecopy(),
             0x3a => self.op_gasprice(),
-            0x3b => self.op_extcodesize(),
-            0x3c => self.op_extcodecopy(),
+            0x3b => self.op_extcodesize(state),
+            0x3c => self.op_extcodecopy(state),
             0x3d => self.op_returndatasize(),
             0x3e => self.op_returndatacopy(),
-            0x3f => self.op_extcodehash(),
+            0x3f => self.op_extcodehash(state),
             // 0x40
             0x40 => self.op_blockhash(),
             0x41 => self.op_coinbase(),
@@ -152,18 +418,28 @@ impl AXISVM {
             0x43 => self.op_number(),
             0x44 => self.op_difficulty(),
             0x45 => self.op_gaslimit(),
+            0x46 => self.op_chainid(),
+            0x47 => self.op_selfbalance(state),
+            0x48 => self.op_basefee(),
+            0x49 => self.op_blobhash(),
+            0x4a => self.op_blobbasefee(),
             // 0x50
             0x50 => self.op_pop(),
             0x51 => self.op_mload(),
             0x52 => self.op_mstore(),
-            0x54 => self.op_sload(contract),
-            0x55 => self.op_sstore(contract),
+            0x53 => self.op_mstore8(),
+            0x54 => self.op_sload(state),
+            0x55 => self.op_sstore(state),
             0x56 => self.op_jump(),
             0x57 => self.op_jumpi(),
             0x58 => self.op_pc(),
             0x59 => self.op_msize(),
             0x5a => self.op_gas(),
             0x5b => self.op_jumpdest(),
+            0x5c => self.op_tload(state),
+            0x5d => self.op_tstore(state),
+            0x5e => self.op_mcopy(),
+            0x5f => self.op_push0(),
             // 0x60, 0x70
             0x60 => self.op_push(1),
             0x61 => self.op_push(2),
@@ -232,196 +508,589 @@ impl AXISVM {
             0x9e => self.op_swap(15),
             0x9f => self.op_swap(16),
             // 0xa0
-            0xa0 => self.op_log0(),
-            0xa1 => self.op_log1(),
-            0xa2 => self.op_log2(),
-            0xa3 => self.op_log3(),
-            0xa4 => self.op_log4(),
+            0xa0 => self.op_log(state, inspector, 0),
+            0xa1 => self.op_log(state, inspector, 1),
+            0xa2 => self.op_log(state, inspector, 2),
+            0xa3 => self.op_log(state, inspector, 3),
+            0xa4 => self.op_log(state, inspector, 4),
             // 0xf0
-            0xf0 => self.op_create(),
-            0xf1 => self.op_call(),
-            0xf2 => self.op_callcode(),
+            0xf0 => self.op_create(state, inspector),
+            0xf1 => self.op_call(state, inspector),
+            0xf2 => self.op_callcode(state, inspector),
             0xf3 => self.op_return(),
-            0xf4 => self.op_delegatecall(),
-            0xf5 => self.op_create2(),
-            0xfa => self.op_staticcall(),
+            0xf4 => self.op_delegatecall(state, inspector),
+            0xf5 => self.op_create2(state, inspector),
+            0xfa => self.op_staticcall(state, inspector),
             0xfd => self.op_revert(),
-            0xff => self.op_selfdestruct(),
-            _ => not_implement_panic(),
+            0xff => self.op_selfdestruct(state, inspector),
+            _ => Err(Halt::InvalidOpcode(opcode)),
         }
+    }
 
+    /// Iterate exec until transaction ends. The origin's nonce is
+    /// incremented and the call value moved from the sender to the callee
+    /// first. State changes made by the code, and the value transfer, are
+    /// committed when it succeeds and rolled back when it reverts or halts
+    /// exceptionally; the nonce and changes made by the caller beforehand,
+    /// like the up-front charge, are kept. A rejected transaction changes
+    /// nothing and commits nothing.
+    pub fn transaction_execute(&mut self, state: &mut WorldState) -> ExecutionOutcome {
+        self.inspect(state, &mut NoopInspector)
+    }
 
+    /// Execute the transaction like `transaction_execute`, calling the
+    /// hooks of `inspector` along the way.
+    pub fn inspect<I: Inspector>(
+        &mut self,
+        state: &mut WorldState,
+        inspector: &mut I,
+    ) -> ExecutionOutcome {
+        let (sender, callee, value) =
+            (self.env.sender, self.env.code_supervisor, self.env.call_value);
+        if state.balance(sender) < value {
+            return ExecutionOutcome::Rejected { error: StateError::InsufficientBalance };
+        }
+        if state.nonce(self.tx.origin) == u64::max_value() {
+            return ExecutionOutcome::Rejected { error: StateError::NonceOverflow };
+        }
 
-  /// Iterate exec until transaction ends
-    pub fn transaction_execute(&mut self, contract: &mut state::AccountState) {
+        // Like the fee paid up front, the nonce stays incremented even if
+        // the code fails
+        state.increment_nonce(self.tx.origin).expect("nonce checked above");
+        if self.schedule.access_lists {
+            self.warm_up(state);
+        }
+        let checkpoint = state.checkpoint();
+        if value.is_zero() {
+            state.touch_account(callee);
+        } else {
+            state.transfer(sender, callee, value).expect("balance checked above");
+        }
+        let halt = match self.precompiles.get(self.env.code_supervisor) {
+            Some(precompile) => self.run_precompile(&*precompile),
+            None => self.run(state, inspector),
+        };
+
+        match halt {
+            Halt::Stop | Halt::Return => {}
+            _ => state.revert_to(checkpoint),
+        }
+        if self.schedule.empty_account_is_dead {
+            state.remove_touched_empty_accounts();
+        }
+        let logs = state.commit();
+        self.outcome(halt, logs)
+    }
+
+    /// Warm the accounts and slots every transaction starts with: the origin,
+    /// the callee, the precompiles, the access list and, since Shanghai, the
+    /// coinbase.
+    fn warm_up(&self, state: &mut WorldState) {
+        state.access_account(self.tx.origin);
+        state.access_account(self.env.code_supervisor);
+        if self.schedule.warm_coinbase {
+            state.access_account(self.block.coinbase);
+        }
+        for address in self.precompiles.addresses() {
+            state.access_account(address);
+        }
+        for item in &self.tx.access_list {
+            state.access_account(item.address);
+            for key in &item.storage_keys {
+                state.access_storage(item.address, *key);
+            }
+        }
+    }
+
+    /// Run the frame's code until it halts
+    fn run<I: Inspector>(&mut self, state: &mut WorldState, inspector: &mut I) -> Halt {
         loop {
             if self.pc >= self.env.code.len() {
-                break;
+                return Halt::Stop;
             }
 
-            if self.exec(contract) {
-                break;
+            if let Err(halt) = self.exec(state, inspector) {
+                return halt;
             }
         }
     }
 
+    /// Run a precompiled contract on the frame's input instead of code
+    fn run_precompile(&mut self, precompile: &dyn Precompile) -> Halt {
+        if self.consume_gas(precompile.gas(&self.env.input)).is_err() {
+            return Halt::OutOfGas;
+        }
+        match precompile.execute(&self.env.input) {
+            Ok(output) => {
+                self.returns = output;
+                Halt::Return
+            }
+            Err(_) => {
+                self.gas = 0;
+                Halt::PrecompileFailure
+            }
+        }
+    }
 
-      // Flag to end the transaction return only true
-          return match opcode {
-            0xf3 => true,
-            _ => false,
-        };
+    /// Build the transaction result. Exceptional halts consume all gas.
+    fn outcome(&mut self, halt: Halt, logs: Vec<Log>) -> ExecutionOutcome {
+        let gas_used = self.gas_limit - self.gas;
+        let gas_limit = self.gas_limit;
+
+        match halt {
+            Halt::Stop | Halt::Return => ExecutionOutcome::Success {
+                gas_used: gas_used - self.refund_amount(gas_used),
+                returns: self.returns.clone(),
+                logs,
+            },
+            Halt::Revert => ExecutionOutcome::Revert {
+                gas_used,
+                returns: self.returns.clone(),
+            },
+            Halt::OutOfGas => ExecutionOutcome::OutOfGas { gas_used: gas_limit },
+            Halt::InvalidOpcode(opcode) => ExecutionOutcome::InvalidOpcode {
+                opcode,
+                gas_used: gas_limit,
+            },
+            Halt::StackUnderflow => ExecutionOutcome::StackUnderflow { gas_used: gas_limit },
+            Halt::StackOverflow => ExecutionOutcome::StackOverflow { gas_used: gas_limit },
+            Halt::InvalidJump => ExecutionOutcome::InvalidJump { gas_used: gas_limit },
+            Halt::StaticViolation => ExecutionOutcome::StaticViolation { gas_used: gas_limit },
+            Halt::ReturnDataOutOfBounds => {
+                ExecutionOutcome::ReturnDataOutOfBounds { gas_used: gas_limit }
+            }
+            Halt::PrecompileFailure => ExecutionOutcome::PrecompileFailure { gas_used: gas_limit },
+        }
+    }
+
+    /// Refund earned by the transaction, capped at a fraction of the gas
+    /// used set by the schedule
+    fn refund_amount(&self, gas_used: usize) -> usize {
+        if self.refund <= 0 {
+            return 0;
+        }
+        (self.refund as usize).min(gas_used / self.schedule.max_refund_quotient)
     }
 
-    fn consume_gas(&mut self, gas: usize) {
+    fn consume_gas(&mut self, gas: usize) -> OpResult {
         if self.gas >= gas {
             self.gas -= gas;
+            Ok(())
         } else {
-            panic!("consume_gas: out of gas!");
+            self.gas = 0;
+            Err(Halt::OutOfGas)
         }
     }
 
+    /// Expand memory to cover `len` bytes from `offset`, charging the
+    /// expansion gas, and return both as `usize`. Zero-length accesses do
+    /// not touch memory.
+    fn expand_memory(&mut self, offset: U256, len: U256) -> Result<(usize, usize), Halt> {
+        let len = as_usize(len)?;
+        if len == 0 {
+            return Ok((0, 0));
+        }
 
+        let offset = as_usize(offset)?;
+        let words = memory::word_count(offset + len);
+        let current = self.memory.words();
+        if words > current {
+            let cost = self.schedule.memory_gas(words) - self.schedule.memory_gas(current);
+            self.consume_gas(cost)?;
+            self.memory.resize(words);
+        }
+        Ok((offset, len))
+    }
 
-    pub fn disassemble(code: &str) {
-        let mut env = Environment::new(
-            Default::default(),
-            Default::default(),
-            1_000_000_000,
-            100_000_000_000_000_000,
-        );
-
+    /// Gas of accessing `address`, which costs `cold` the first time in the
+    /// transaction and the warm price afterwards (EIP-2929). Without access
+    /// lists every access costs `cold`.
+    fn account_access_cost(&self, state: &mut WorldState, address: H160, cold: usize) -> usize {
+        if self.schedule.access_lists && !state.access_account(address) {
+            self.schedule.warm_access
+        } else {
+            cold
+        }
+    }
 
-        fn push_assembly(&mut self, mnemonic: &str) {
-            self.asm.push(mnemonic.to_string());
+    /// Gas of reading a storage slot of `address`, priced like
+    /// `account_access_cost` with the schedule's SLOAD price as cold price
+    fn storage_access_cost(&self, state: &mut WorldState, address: H160, key: U256) -> usize {
+        if self.schedule.access_lists && !state.access_storage(address, key) {
+            self.schedule.warm_access
+        } else {
+            self.schedule.sload
         }
+    }
+
+    /// Static cost plus the per-word cost of copying `length` bytes
+    fn copy_cost(&self, base: usize, length: U256) -> Result<usize, Halt> {
+        Ok(base + self.schedule.copy_word * memory::word_count(as_usize(length)?))
+    }
+}
 
-        env.set_code(util::str_to_bytes(code));
-        let mut axvm = AXISVM::new(env);
-        let mut contract = state::AccountState::new(code.to_string());
-        axvm.transaction_execute(&mut contract);
 
-        for mnemonic in axvm.asm {
-            println!("{}", mnemonic);
-        }
+/// Read access to the frame for inspectors
+impl AXISVM {
+    /// Offset of the next instruction
+    pub fn pc(&self) -> usize {
+        self.pc
     }
 
+    /// Opcode at pc, if pc is inside the code
+    pub fn opcode(&self) -> Option<u8> {
+        self.env.code.get(self.pc).cloned()
+    }
 
-}
+    pub fn code(&self) -> &[u8] {
+        &self.env.code
+    }
+
+    pub fn input(&self) -> &[u8] {
+        &self.env.input
+    }
 
+    /// Account whose code the frame runs on behalf of
+    pub fn address(&self) -> H160 {
+        self.env.code_supervisor
+    }
+
+    pub fn gas(&self) -> usize {
+        self.gas
+    }
+
+    /// Gas the frame started with
+    pub fn gas_limit(&self) -> usize {
+        self.gas_limit
+    }
+
+    /// Refund counter of the frame
+    pub fn refund(&self) -> i64 {
+        self.refund
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.is_static
+    }
 
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Output of the last completed sub-call
+    pub fn return_data(&self) -> &[u8] {
+        &self.return_data
+    }
+
+    pub fn spec(&self) -> SpecId {
+        self.spec
+    }
+
+    pub fn schedule(&self) -> &GasSchedule {
+        &self.schedule
+    }
+}
 
 /// 0x30: Operation related to execution environment Part 1
 impl AXISVM {
     /// 0x30: address of the executing contract
-    fn op_address(&mut self) {
-        self.consume_gas(2);
-        self.push_assembly("ADDRESS");
+    fn op_address(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
         let address = util::h160_to_u256(&self.env.code_supervisor);
-        self.push(address);
+        self.push(address)?;
+        Ok(())
     }
 
     /// 0x31: Get balance of the given account.
-    fn op_balance(&mut self) {
-        self.consume_gas(400);
-        self.push_assembly("BALANCE");
-        let address = util::u256_to_h160(&self.pop());       
-        not_implement_panic();
+    fn op_balance(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        let address = util::u256_to_h160(&self.pop()?);
+        let cost = self.account_access_cost(state, address, self.schedule.balance);
+        self.consume_gas(cost)?;
+        self.push(state.balance(address))?;
+        Ok(())
     }
 
-    fn op_origin(&mut self) {
-        self.push_assembly("ORIGIN");
-        not_implement_panic();
+    /// 0x32: account that signed the transaction
+    fn op_origin(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let origin = util::h160_to_u256(&self.tx.origin);
+        self.push(origin)?;
+        Ok(())
     }
 
-    fn op_caller(&mut self) {
-        self.consume_gas(2);
-        self.push_assembly("CALLER");
-        self.push(util::h160_to_u256(&self.env.sender));
+    fn op_caller(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        self.push(util::h160_to_u256(&self.env.sender))?;
+        Ok(())
     }
 
-    fn op_callvalue(&mut self) {
-        self.push_assembly("CALLVALUE");
-        not_implement_panic();
+    /// 0x34: value transferred with this call
+    fn op_callvalue(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let call_value = self.env.call_value;
+        self.push(call_value)?;
+        Ok(())
     }
 
  /// 0x35: Push the value popped from the stack as start and push 32 bytes of data from the start position of input to the position of start + 32 to the stack.
-    fn op_calldataload(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("CALLDATALOAD");
-        let start = self.pop().as_u32() as usize;
-        let bytes: [u8; 32] = util::slice_to_array(&self.env.input[start..]);
-        self.push(bytes.into());
+    fn op_calldataload(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let start = self.pop()?;
+        let mut bytes = [0u8; 32];
+        if start < U256::from(self.env.input.len()) {
+            // Bytes past the end of input read as zero
+            let start = start.low_u64() as usize;
+            let end = (start + 32).min(self.env.input.len());
+            bytes[..end - start].copy_from_slice(&self.env.input[start..end]);
+        }
+        self.push(bytes.into())?;
+        Ok(())
     }
 
 /// 0x36: Push the data size stored in input to stack
-    fn op_calldatasize(&mut self) {
-        self.consume_gas(2);
-        self.push_assembly("CALLDATASIZE");
+    fn op_calldatasize(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
         let size = self.env.input.len();
-        self.push(size.into());
+        self.push(size.into())?;
+        Ok(())
     }
 
-    /// 0x37:
-    fn op_calldatacopy(&mut self) {
-        self.push_assembly("CALLDATACOPY");
-        not_implement_panic();
+    /// 0x37: Copy input data into memory
+    fn op_calldatacopy(&mut self) -> OpResult {
+        self.stack.check_pop_push(3, 0)?;
+        let dest_offset = self.pop()?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+
+        let cost = self.copy_cost(self.schedule.very_low, length)?;
+        self.consume_gas(cost)?;
+        let (dest_offset, length) = self.expand_memory(dest_offset, length)?;
+        self.memory.copy_padded(dest_offset, &self.env.input, offset, length);
+        Ok(())
     }
 
-    /// 0x38:
-    fn op_codesize(&mut self) {
-        self.push_assembly("CODESIZE");
-        not_implement_panic();
+    /// 0x38: Push the size of the running code
+    fn op_codesize(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let size = self.env.code.len();
+        self.push(size.into())?;
+        Ok(())
     }
 
    /// 0x39: Copy the code deployed to the contract
-    fn op_codecopy(&mut self) {
-        self.consume_gas(9); // ???
-        self.push_assembly("CODECOPY");
-        let dest_offset = self.pop().as_u32() as usize;
-        let offset = self.pop().as_u32() as usize;
-        let length = self.pop().as_u32() as usize;
+    fn op_codecopy(&mut self) -> OpResult {
+        self.stack.check_pop_push(3, 0)?;
+        let dest_offset = self.pop()?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+
+        let cost = self.copy_cost(self.schedule.very_low, length)?;
+        self.consume_gas(cost)?;
+        let (dest_offset, length) = self.expand_memory(dest_offset, length)?;
+        self.memory.copy_padded(dest_offset, &self.env.code, offset, length);
+        Ok(())
+    }
+
+    /// 0x3a: gas price of the transaction
+    fn op_gasprice(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let gas_price = self.tx.gas_price;
+        self.push(gas_price)?;
+        Ok(())
+    }
 
-        for i in 0..length {
-            let b = self.env.code[offset + i];
-            self.memory.insert(dest_offset + i, b);
+    /// 0x3b: Push the code size of the given account
+    fn op_extcodesize(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        let address = util::u256_to_h160(&self.pop()?);
+        let cost = self.account_access_cost(state, address, self.schedule.ext_code_size);
+        self.consume_gas(cost)?;
+        let size = state.code(address).len();
+        self.push(size.into())?;
+        Ok(())
+    }
+
+    /// 0x3c: Copy the code of the given account into memory
+    fn op_extcodecopy(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(4, 0)?;
+        let address = util::u256_to_h160(&self.pop()?);
+        let dest_offset = self.pop()?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+
+        let access = self.account_access_cost(state, address, self.schedule.ext_code_copy);
+        let cost = self.copy_cost(access, length)?;
+        self.consume_gas(cost)?;
+        let (dest_offset, length) = self.expand_memory(dest_offset, length)?;
+        self.memory.copy_padded(dest_offset, state.code(address), offset, length);
+        Ok(())
+    }
+
+    /// 0x3d: Push the size of the output of the last sub-call
+    fn op_returndatasize(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let size = self.return_data.len();
+        self.push(size.into())?;
+        Ok(())
+    }
+
+    /// 0x3e: Copy the output of the last sub-call into memory. Reading past
+    /// its end is an exceptional halt (EIP-211).
+    fn op_returndatacopy(&mut self) -> OpResult {
+        self.stack.check_pop_push(3, 0)?;
+        let dest_offset = self.pop()?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+
+        let end = offset.overflowing_add(length);
+        if end.1 || end.0 > U256::from(self.return_data.len()) {
+            return Err(Halt::ReturnDataOutOfBounds);
         }
+
+        let cost = self.copy_cost(self.schedule.very_low, length)?;
+        self.consume_gas(cost)?;
+        let (dest_offset, length) = self.expand_memory(dest_offset, length)?;
+        self.memory.copy_padded(dest_offset, &self.return_data, offset, length);
+        Ok(())
+    }
+
+    /// 0x3f: Push the code hash of the given account, or zero if it is empty
+    fn op_extcodehash(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        let address = util::u256_to_h160(&self.pop()?);
+        let cost = self.account_access_cost(state, address, self.schedule.ext_code_hash);
+        self.consume_gas(cost)?;
+        let code_hash = state.code_hash(address);
+        self.push(U256::from(code_hash.as_bytes()))?;
+        Ok(())
+    }
+}
+
+
+/// 0x40: Block information
+impl AXISVM {
+    /// 0x40: hash of one of the 256 most recent blocks, zero otherwise
+    fn op_blockhash(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        self.consume_gas(self.schedule.blockhash)?;
+        let number = self.pop()?;
+        let hash = self.block.block_hash(number);
+        self.push(U256::from(hash.as_bytes()))?;
+        Ok(())
     }
 
-    /// 0x3a:
-    fn op_gasprice(&mut self) {
-        self.push_assembly("GASPRICE");
-        not_implement_panic();
+    /// 0x41: beneficiary of the block
+    fn op_coinbase(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let coinbase = util::h160_to_u256(&self.block.coinbase);
+        self.push(coinbase)?;
+        Ok(())
     }
 
-    /// 0x3b:
-    fn op_extcodesize(&mut self) {
-        self.push_assembly("EXTCODESIZE");
-        not_implement_panic();
+    /// 0x42: block timestamp
+    fn op_timestamp(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let timestamp = self.block.timestamp;
+        self.push(timestamp.into())?;
+        Ok(())
     }
 
-    /// 0x3c:
-    fn op_extcodecopy(&mut self) {
-        self.push_assembly("EXTCODECOPY");
-        not_implement_panic();
+    /// 0x43: block number
+    fn op_number(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let number = self.block.number;
+        self.push(number.into())?;
+        Ok(())
     }
 
-    /// 0x3d:
-    fn op_returndatasize(&mut self) {
-        self.push_assembly("RETURNDATASIZE");
-        not_implement_panic();
+    /// 0x44: block difficulty
+    fn op_difficulty(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let difficulty = self.block.difficulty;
+        self.push(difficulty)?;
+        Ok(())
     }
 
-    /// 0x3e:
-    fn op_returndatacopy(&mut self) {
-        self.push_assembly("RETURNDATACOPY");
-        not_implement_panic();
+    /// 0x45: block gas limit
+    fn op_gaslimit(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let gas_limit = self.block.gas_limit;
+        self.push(gas_limit.into())?;
+        Ok(())
     }
 
-    /// 0x3f:
-    fn op_extcodehash(&mut self) {
-        self.push_assembly("EXTCODEHASH");
-        not_implement_panic();
+    /// 0x46: chain id (EIP-1344)
+    fn op_chainid(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let chain_id = self.tx.chain_id;
+        self.push(chain_id.into())?;
+        Ok(())
+    }
+
+    /// 0x47: balance of the executing contract (EIP-1884)
+    fn op_selfbalance(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.low)?;
+        let balance = state.balance(self.env.code_supervisor);
+        self.push(balance)?;
+        Ok(())
+    }
+
+    /// 0x48: base fee of the block (EIP-3198)
+    fn op_basefee(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let base_fee = self.block.base_fee;
+        self.push(base_fee)?;
+        Ok(())
+    }
+
+    /// 0x49: versioned hash of the transaction's blob at the given index,
+    /// zero past the last blob (EIP-4844)
+    fn op_blobhash(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let index = self.pop()?;
+        let hash = if index < U256::from(self.tx.blob_hashes.len()) {
+            self.tx.blob_hashes[index.low_u64() as usize]
+        } else {
+            H256::zero()
+        };
+        self.push(U256::from(hash.as_bytes()))?;
+        Ok(())
+    }
+
+    /// 0x4a: blob base fee of the block (EIP-7516)
+    fn op_blobbasefee(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let blob_base_fee = self.block.blob_base_fee;
+        self.push(blob_base_fee)?;
+        Ok(())
     }
 }
 
@@ -429,139 +1098,519 @@ impl AXISVM {
 /// 0x10: Condition, bit operation
 impl AXISVM {
     /// 0x10: operand1 < operand2
-    fn op_lt(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("LT");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
+    fn op_lt(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
         if operand1 < operand2 {
-            self.push(U256::from(1));
+            self.push(U256::from(1))?;
         } else {
-            self.push(U256::from(0));
+            self.push(U256::from(0))?;
         }
+        Ok(())
     }
 
     /// 0x11: operand1 > operand2
-    fn op_gt(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("GT");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
+    fn op_gt(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
         if operand1 > operand2 {
-            self.push(U256::from(1));
+            self.push(U256::from(1))?;
         } else {
-            self.push(U256::from(0));
+            self.push(U256::from(0))?;
         }
+        Ok(())
     }
 
-    fn op_slt(&mut self) {
-        self.push_assembly("SLT");
-        not_implement_panic();
+    /// 0x12: operand1 < operand2, both signed
+    fn op_slt(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = U256::from(arith::slt(operand1, operand2) as u8);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_sgt(&mut self) {
-        self.push_assembly("SGT");
-        not_implement_panic();
+    /// 0x13: operand1 > operand2, both signed
+    fn op_sgt(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = U256::from(arith::slt(operand2, operand1) as u8);
+        self.push(result)?;
+        Ok(())
     }
 
     /// 0x14: operand1 == operand2
-    fn op_eq(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("EQ");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
+    fn op_eq(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
         if operand1 == operand2 {
-            self.push(U256::from(1));
+            self.push(U256::from(1))?;
         } else {
-            self.push(U256::from(0));
+            self.push(U256::from(0))?;
         }
+        Ok(())
     }
 
     /// 0x15: operand1 == 0
-    fn op_is_zero(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("ISZERO");
-        let operand1 = self.pop();
+    fn op_is_zero(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
         if operand1 == U256::from(0) {
-            self.push(U256::from(1));
+            self.push(U256::from(1))?;
         } else {
-            self.push(U256::from(0));
+            self.push(U256::from(0))?;
         }
+        Ok(())
     }
 
     /// AND: operand1 & operand2
-    fn op_and(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("AND");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
+    fn op_and(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
         let result = operand1 & operand2;
-        self.push(result);
+        self.push(result)?;
+        Ok(())
     }
 
     /// OR operand1 | operand2
-    fn op_or(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("OR");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
+    fn op_or(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
         let result = operand1 | operand2;
-        self.push(result);
+        self.push(result)?;
+        Ok(())
     }
 
     /// XOR: operand1 ^ operand2
-    fn op_xor(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("XOR");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
+    fn op_xor(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
         let result = operand1 ^ operand2;
-        self.push(result);
+        self.push(result)?;
+        Ok(())
     }
 
     /// NOT  ~operand1
-    fn op_not(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("NOT");
-        let operand1 = self.pop();
+    fn op_not(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
         let result = !operand1;
-        self.push(result);
+        self.push(result)?;
+        Ok(())
     }
 
     /// BYESHIFT 0x1a: operand2 の operand1
-    fn op_byte(&mut self) {
-        // y = (operand2 >> (248 - operand1 * 8)) & 0xFF
-        self.consume_gas(3);
-        self.push_assembly("BYTE");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
-        let mask = U256::from(0xff);
-        let index = 248 - (operand1.as_u32() as usize) * 8;
-        let result = (operand2 >> index) & mask;
-        self.push(result);
+    fn op_byte(&mut self) -> OpResult {
+        // y = (operand2 >> (248 - operand1 * 8)) & 0xFF, or 0 once operand1 >= 32
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::byte(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_shl(&mut self) {
-        self.push_assembly("SHL");
-        not_implement_panic();
+    /// 0x1b: operand2 << operand1
+    fn op_shl(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::shl(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_shr(&mut self) {
-        self.push_assembly("SHR");
-        not_implement_panic();
+    /// 0x1c: operand2 >> operand1
+    fn op_shr(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::shr(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_sar(&mut self) {
-        self.push_assembly("SAR");
-        not_implement_panic();
+    /// 0x1d: operand2 >> operand1, sign-filled
+    fn op_sar(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::sar(operand1, operand2);
+        self.push(result)?;
+        Ok(())
+    }
+}
+
+/// 0x50: Stack and memory operations
+impl AXISVM {
+    /// 0x50: discard the top of the stack
+    fn op_pop(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 0)?;
+        self.consume_gas(self.schedule.base)?;
+        self.pop()?;
+        Ok(())
+    }
+
+    /// 0x51: push the word at memory[offset..offset + 32]
+    fn op_mload(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let offset = self.pop()?;
+        let (offset, _) = self.expand_memory(offset, U256::from(32))?;
+        let value = self.memory.load_word(offset);
+        self.push(value)?;
+        Ok(())
+    }
+
+    /// 0x52: store a word at memory[offset..offset + 32]
+    fn op_mstore(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 0)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let offset = self.pop()?;
+        let value = self.pop()?;
+        let (offset, _) = self.expand_memory(offset, U256::from(32))?;
+        self.memory.store_word(offset, value);
+        Ok(())
+    }
+
+    /// 0x53: store the low byte of a word at memory[offset]
+    fn op_mstore8(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 0)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let offset = self.pop()?;
+        let value = self.pop()?;
+        let (offset, _) = self.expand_memory(offset, U256::one())?;
+        self.memory.store_byte(offset, value);
+        Ok(())
+    }
+
+    /// 0x54: push the value of a storage slot of the executing contract
+    fn op_sload(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        let key = self.pop()?;
+        let cost = self.storage_access_cost(state, self.env.code_supervisor, key);
+        self.consume_gas(cost)?;
+        let value = state.storage(self.env.code_supervisor, key);
+        self.push(value)?;
+        Ok(())
+    }
+
+    /// 0x55: write a storage slot of the executing contract. Under net gas
+    /// metering, gas and refunds depend on the slot's value at the start of
+    /// the transaction (original), now (current) and after the write (new),
+    /// as in EIP-2200; otherwise only on current and new.
+    fn op_sstore(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(2, 0)?;
+        if self.is_static {
+            return Err(Halt::StaticViolation);
+        }
+        if self.schedule.sstore_net_metering && self.gas <= self.schedule.sstore_sentry {
+            return Err(Halt::OutOfGas);
+        }
+        let key = self.pop()?;
+        let new = self.pop()?;
+
+        let address = self.env.code_supervisor;
+        let current = state.storage(address, key);
+
+        // EIP-2929: the first access to the slot costs a cold SLOAD on top
+        if self.schedule.access_lists && state.access_storage(address, key) {
+            self.consume_gas(self.schedule.sload)?;
+        }
+
+        if self.schedule.sstore_net_metering {
+            let original = state.original_storage(address, key);
+            self.sstore_net_metered(original, current, new)?;
+        } else if current.is_zero() && !new.is_zero() {
+            self.consume_gas(self.schedule.sstore_set)?;
+        } else {
+            self.consume_gas(self.schedule.sstore_reset)?;
+            if !current.is_zero() && new.is_zero() {
+                self.refund += self.schedule.sstore_clears_refund;
+            }
+        }
+
+        state.set_storage(address, key, new);
+        Ok(())
+    }
+
+    /// Charge an SSTORE under EIP-2200 net gas metering. With access lists
+    /// the slot is warm by now, so reads are priced as warm accesses.
+    fn sstore_net_metered(&mut self, original: U256, current: U256, new: U256) -> OpResult {
+        let sload = if self.schedule.access_lists {
+            self.schedule.warm_access
+        } else {
+            self.schedule.sload
+        };
+        let clears_refund = self.schedule.sstore_clears_refund;
+
+        if current == new {
+            return self.consume_gas(sload);
+        }
+
+        if original == current {
+            if original.is_zero() {
+                self.consume_gas(self.schedule.sstore_set)?;
+            } else {
+                self.consume_gas(self.schedule.sstore_reset)?;
+                if new.is_zero() {
+                    self.refund += clears_refund;
+                }
+            }
+        } else {
+            // The slot is already dirty in this transaction
+            self.consume_gas(sload)?;
+            if !original.is_zero() {
+                if current.is_zero() {
+                    self.refund -= clears_refund;
+                } else if new.is_zero() {
+                    self.refund += clears_refund;
+                }
+            }
+            if original == new {
+                if original.is_zero() {
+                    self.refund += (self.schedule.sstore_set - sload) as i64;
+                } else {
+                    self.refund += (self.schedule.sstore_reset - sload) as i64;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 0x56: jump to the destination on top of the stack
+    fn op_jump(&mut self) -> OpResult {
+        self.stack.check_pop_push(1, 0)?;
+        self.consume_gas(self.schedule.mid)?;
+        let destination = self.pop()?;
+        self.jump_to(destination)
+    }
+
+    /// 0x57: jump to the destination if the condition is non-zero
+    fn op_jumpi(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 0)?;
+        self.consume_gas(self.schedule.high)?;
+        let destination = self.pop()?;
+        let condition = self.pop()?;
+        if condition.is_zero() {
+            Ok(())
+        } else {
+            self.jump_to(destination)
+        }
+    }
+
+    /// 0x58: push the offset of this instruction
+    fn op_pc(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let pc = self.pc - 1;
+        self.push(pc.into())?;
+        Ok(())
+    }
+
+    /// 0x59: push the memory size in bytes
+    fn op_msize(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let size = self.memory.len();
+        self.push(size.into())?;
+        Ok(())
+    }
+
+    /// 0x5a: push the gas remaining after this instruction
+    fn op_gas(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        let gas = self.gas;
+        self.push(gas.into())?;
+        Ok(())
+    }
+
+    /// 0x5b: mark a valid jump destination
+    fn op_jumpdest(&mut self) -> OpResult {
+        self.consume_gas(self.schedule.jumpdest)?;
+        Ok(())
+    }
+
+    /// 0x5c: push the value of a transient storage slot of the executing
+    /// contract (EIP-1153)
+    fn op_tload(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(1, 1)?;
+        self.consume_gas(self.schedule.tload)?;
+        let key = self.pop()?;
+        let value = state.transient_storage(self.env.code_supervisor, key);
+        self.push(value)?;
+        Ok(())
+    }
+
+    /// 0x5d: write a transient storage slot of the executing contract. The
+    /// slot is cleared when the transaction ends.
+    fn op_tstore(&mut self, state: &mut WorldState) -> OpResult {
+        self.stack.check_pop_push(2, 0)?;
+        if self.is_static {
+            return Err(Halt::StaticViolation);
+        }
+        self.consume_gas(self.schedule.tstore)?;
+        let key = self.pop()?;
+        let value = self.pop()?;
+        state.set_transient_storage(self.env.code_supervisor, key, value);
+        Ok(())
+    }
+
+    /// 0x5e: copy memory[source..source + length] to memory[dest..dest +
+    /// length]; the ranges may overlap (EIP-5656)
+    fn op_mcopy(&mut self) -> OpResult {
+        self.stack.check_pop_push(3, 0)?;
+        let dest_offset = self.pop()?;
+        let source_offset = self.pop()?;
+        let length = self.pop()?;
+
+        let cost = self.copy_cost(self.schedule.very_low, length)?;
+        self.consume_gas(cost)?;
+        let (source_offset, _) = self.expand_memory(source_offset, length)?;
+        let (dest_offset, length) = self.expand_memory(dest_offset, length)?;
+        self.memory.copy_within(source_offset, dest_offset, length);
+        Ok(())
+    }
+
+    /// Move the program counter, rejecting anything but a JUMPDEST
+    fn jump_to(&mut self, destination: U256) -> OpResult {
+        if destination >= U256::from(self.env.code.len()) {
+            return Err(Halt::InvalidJump);
+        }
+
+        let destination = destination.low_u64() as usize;
+        if !self.jumps.is_valid(destination) {
+            return Err(Halt::InvalidJump);
+        }
+        self.pc = destination;
+        Ok(())
+    }
+}
+
+/// 0x60: Push, duplication and exchange operations
+impl AXISVM {
+    /// 0x5f: push the constant 0 (EIP-3855)
+    fn op_push0(&mut self) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.base)?;
+        self.push(U256::zero())?;
+        Ok(())
+    }
+
+    /// 0x60 - 0x7f: push the next n bytes of code as a big-endian word.
+    /// Immediates cut off by the end of the code are zero-padded.
+    fn op_push(&mut self, n: usize) -> OpResult {
+        self.stack.check_pop_push(0, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+
+        let mut immediate = [0u8; 32];
+        let start = self.pc.min(self.env.code.len());
+        let end = (self.pc + n).min(self.env.code.len());
+        immediate[..end - start].copy_from_slice(&self.env.code[start..end]);
+        let immediate = &immediate[..n];
+        self.pc += n;
+
+        self.push(U256::from(immediate))?;
+        Ok(())
+    }
+
+    /// 0x80 - 0x8f: duplicate the n-th stack item
+    fn op_dup(&mut self, n: usize) -> OpResult {
+        self.stack.check_pop_push(n, n + 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let value = self.stack.peek(n - 1)?;
+        self.push(value)?;
+        Ok(())
+    }
+
+    /// 0x90 - 0x9f: exchange the top of the stack with the (n + 1)-th item
+    fn op_swap(&mut self, n: usize) -> OpResult {
+        self.stack.check_pop_push(n + 1, n + 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let top = self.stack.peek(0)?;
+        let other = self.stack.peek(n)?;
+        self.stack.set(0, other)?;
+        self.stack.set(n, top)?;
+        Ok(())
+    }
+}
+
+/// 0xa0: Logging operations
+impl AXISVM {
+    /// 0xa0 - 0xa4: emit a log of memory[offset..offset + length] with n
+    /// topics. The log is dropped if this frame or any caller reverts.
+    fn op_log<I: Inspector>(
+        &mut self,
+        state: &mut WorldState,
+        inspector: &mut I,
+        n: usize,
+    ) -> OpResult {
+        self.stack.check_pop_push(n + 2, 0)?;
+        if self.is_static {
+            return Err(Halt::StaticViolation);
+        }
+        let offset = self.pop()?;
+        let length = self.pop()?;
+        let mut topics = Vec::with_capacity(n);
+        for _ in 0..n {
+            let mut topic = [0u8; 32];
+            self.pop()?.to_big_endian(&mut topic);
+            topics.push(H256::from(topic));
+        }
+
+        let cost = self.schedule.log
+            + self.schedule.log_topic * n
+            + self.schedule.log_data * as_usize(length)?;
+        self.consume_gas(cost)?;
+        let (offset, length) = self.expand_memory(offset, length)?;
+        let log = Log {
+            address: self.env.code_supervisor,
+            topics,
+            data: self.memory.get(offset, length).to_vec(),
+        };
+        inspector.log(&log);
+        state.log(log);
+        Ok(())
     }
 }
 
 /// 0x20: Cryptographic operation
 impl AXISVM {
-    fn op_sha3(&mut self) {
-        self.push_assembly("SHA3");
-        not_implement_panic();
+    /// 0x20: Keccak-256 of memory[offset..offset + length]
+    fn op_sha3(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+        let cost =
+            self.schedule.sha3 + self.schedule.sha3_word * memory::word_count(as_usize(length)?);
+        self.consume_gas(cost)?;
+        let (offset, length) = self.expand_memory(offset, length)?;
+        let digest = hash::keccak256(self.memory.get(offset, length));
+        self.push(U256::from(digest.as_bytes()))?;
+        Ok(())
     }
 }
 
@@ -575,90 +1624,652 @@ impl AXISVM {
 // Arithmatic Operations
 
 impl AXISVM {
-    /// 0x00: nop
-    fn op_stop(&mut self) {
-        self.push_assembly("STOP");
+    /// 0x00: halt execution
+    fn op_stop(&mut self) -> OpResult {
+        Err(Halt::Stop)
     }
 
     ///0x01: add {operand1 (1st stack) + operand2 (2nd stack)}
-    fn op_add(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("ADD");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
-        let result = operand1 + operand2;
-        self.push(result);
+    fn op_add(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = operand1.overflowing_add(operand2).0;
+        self.push(result)?;
+        Ok(())
     }
 
     /// 0x02: MUL
-    fn op_mul(&mut self) {
-        self.consume_gas(5);
-        self.push_assembly("MUL");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
-        let result = operand1 * operand2;
-        self.push(result);
+    fn op_mul(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = operand1.overflowing_mul(operand2).0;
+        self.push(result)?;
+        Ok(())
     }
 
     /// 0x03: SUB
-    fn op_sub(&mut self) {
-        self.consume_gas(3);
-        self.push_assembly("SUB");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
-        let result = operand1 - operand2;
-        self.push(result);
+    fn op_sub(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.very_low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = operand1.overflowing_sub(operand2).0;
+        self.push(result)?;
+        Ok(())
     }
 
     /// 0x04: DIV
-    fn op_div(&mut self) {
-        self.consume_gas(5);
-        self.push_assembly("DIV");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
-        let result = operand1 / operand2;
-        self.push(result);
+    fn op_div(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::div(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_sdiv(&mut self) {
-        self.push_assembly("SDIV");
-        not_implement_panic();
+    /// 0x05: SDIV, signed division
+    fn op_sdiv(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::sdiv(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_mod(&mut self) {
-        self.push_assembly("MOD");
-        not_implement_panic();
+    /// 0x06: MOD
+    fn op_mod(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::rem(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_smod(&mut self) {
-        self.push_assembly("SMOD");
-        not_implement_panic();
+    /// 0x07: SMOD, signed modulo
+    fn op_smod(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::smod(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_addmod(&mut self) {
-        self.push_assembly("ADDMOD");
-        not_implement_panic();
+    /// 0x08: ADDMOD, (operand1 + operand2) % modulus
+    fn op_addmod(&mut self) -> OpResult {
+        self.stack.check_pop_push(3, 1)?;
+        self.consume_gas(self.schedule.mid)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let modulus = self.pop()?;
+        let result = arith::addmod(operand1, operand2, modulus);
+        self.push(result)?;
+        Ok(())
     }
 
-    fn op_mulmod(&mut self) {
-        self.push_assembly("MULMOD");
-        not_implement_panic();
+    /// 0x09: MULMOD, (operand1 * operand2) % modulus
+    fn op_mulmod(&mut self) -> OpResult {
+        self.stack.check_pop_push(3, 1)?;
+        self.consume_gas(self.schedule.mid)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let modulus = self.pop()?;
+        let result = arith::mulmod(operand1, operand2, modulus);
+        self.push(result)?;
+        Ok(())
     }
 
     /// 0x0a: EXP
-    fn op_exp(&mut self) {
-        self.consume_gas(10);
-        self.push_assembly("EXP");
-        let operand1 = self.pop();
-        let operand2 = self.pop();
-        let result = operand1.pow(operand2);
-        self.push(result);
+    fn op_exp(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.high)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        self.consume_gas(self.schedule.exp_byte * ((operand2.bits() + 7) / 8))?;
+        let result = operand1.overflowing_pow(operand2).0;
+        self.push(result)?;
+        Ok(())
     }
 
-    /// 0x0b:
-    fn op_sig_next_end(&mut self) {
-        self.push_assembly("SIGNEXTEND");
-        not_implement_panic();
+    /// 0x0b: SIGNEXTEND, extend the sign of the (operand1 + 1)-byte value operand2
+    fn op_sig_next_end(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 1)?;
+        self.consume_gas(self.schedule.low)?;
+        let operand1 = self.pop()?;
+        let operand2 = self.pop()?;
+        let result = arith::signextend(operand1, operand2);
+        self.push(result)?;
+        Ok(())
     }
 }
 
+
+/// 0xf0: System operations
+impl AXISVM {
+    /// 0xf0: create a contract at an address derived from the sender and
+    /// its nonce
+    fn op_create<I: Inspector>(&mut self, state: &mut WorldState, inspector: &mut I) -> OpResult {
+        self.create(state, inspector, CreateKind::Create)
+    }
+
+    /// 0xf5: create a contract at an address derived from the sender, a
+    /// salt and the init code
+    fn op_create2<I: Inspector>(&mut self, state: &mut WorldState, inspector: &mut I) -> OpResult {
+        self.create(state, inspector, CreateKind::Create2)
+    }
+
+    /// Run init code in a new frame and install the code it returns at the
+    /// new address. Pushes the address on success, 0 otherwise.
+    fn create<I: Inspector>(
+        &mut self,
+        state: &mut WorldState,
+        inspector: &mut I,
+        kind: CreateKind,
+    ) -> OpResult {
+        self.stack.check_pop_push(if kind == CreateKind::Create2 { 4 } else { 3 }, 1)?;
+        if self.is_static {
+            return Err(Halt::StaticViolation);
+        }
+
+        let value = self.pop()?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+        let salt = match kind {
+            CreateKind::Create2 => Some(self.pop()?),
+            CreateKind::Create => None,
+        };
+
+        self.consume_gas(self.schedule.create)?;
+        let init_code_len = as_usize(length)?;
+        if self.schedule.max_initcode_size.map_or(false, |max| init_code_len > max) {
+            return Err(Halt::OutOfGas);
+        }
+        let mut word_cost = self.schedule.initcode_word;
+        if salt.is_some() {
+            word_cost += self.schedule.create2_word;
+        }
+        self.consume_gas(word_cost * memory::word_count(init_code_len))?;
+        let (offset, length) = self.expand_memory(offset, length)?;
+        let init_code = self.memory.get(offset, length).to_vec();
+
+        // EIP-150: the init code gets all but one 64th of the remaining gas;
+        // before it, all of it
+        let gas = if self.schedule.call_all_but_one_64th {
+            self.gas - self.gas / 64
+        } else {
+            self.gas
+        };
+        self.consume_gas(gas)?;
+
+        self.return_data.clear();
+        let creator = self.env.code_supervisor;
+        if self.depth >= CALL_DEPTH_LIMIT || state.balance(creator) < value {
+            self.gas += gas;
+            return self.push(U256::zero());
+        }
+        let nonce = match state.increment_nonce(creator) {
+            Ok(nonce) => nonce,
+            Err(_) => {
+                self.gas += gas;
+                return self.push(U256::zero());
+            }
+        };
+
+        let address = match salt {
+            Some(salt) => {
+                let mut salt_bytes = [0u8; 32];
+                salt.to_big_endian(&mut salt_bytes);
+                world::create2_address(creator, H256::from(salt_bytes), hash::keccak256(&init_code))
+            }
+            None => world::create_address(creator, nonce),
+        };
+
+        // The new address stays warm even if the creation fails (EIP-2929)
+        if self.schedule.access_lists {
+            state.access_account(address);
+        }
+
+        // An address already holding code or a nonce cannot be reused; the
+        // forwarded gas is lost
+        if state.nonce(address) != 0 || !state.code(address).is_empty() {
+            return self.push(U256::zero());
+        }
+
+        let checkpoint = state.checkpoint();
+        state.create_account(address);
+        if self.schedule.create_nonce_one {
+            state
+                .increment_nonce(address)
+                .expect("nonce of a new account cannot overflow");
+        }
+        state
+            .transfer(creator, address, value)
+            .expect("balance checked before the creation");
+
+        // Init code runs once, so its analysis is not cached
+        let jumps = Rc::new(JumpTable::analyze(&init_code));
+        let env = Environment {
+            code: init_code,
+            input: Vec::new(),
+            code_supervisor: address,
+            sender: creator,
+            call_value: value,
+        };
+        let mut frame = self.child_frame(env, jumps, gas, false);
+        inspector.create(self, &frame.create_inputs(kind));
+        let mut halt = frame.run(state, inspector);
+
+        if halt == Halt::Stop || halt == Halt::Return {
+            let code = &frame.returns;
+            let deposit = self.schedule.code_deposit_byte * code.len();
+            let too_large = self.schedule.max_code_size.map_or(false, |max| code.len() > max);
+            let rejected = self.schedule.reject_ef_code && code.first() == Some(&0xef);
+            let unpaid = frame.gas < deposit;
+            if too_large || rejected || (unpaid && self.schedule.code_deposit_oog_fails) {
+                // A rejected deployment fails like running out of gas
+                frame.gas = 0;
+                frame.returns.clear();
+                halt = Halt::OutOfGas;
+            } else if unpaid {
+                // Frontier: the contract is created without code, for free
+                frame.returns.clear();
+            } else {
+                frame.gas -= deposit;
+            }
+        }
+        inspector.create_end(
+            &frame.create_inputs(kind),
+            &FrameResult { halt, gas_left: frame.gas, output: &frame.returns },
+        );
+
+        match halt {
+            Halt::Stop | Halt::Return => {
+                let code = ::std::mem::replace(&mut frame.returns, Vec::new());
+                state.set_code(address, code);
+                self.gas += frame.gas;
+                self.refund += frame.refund;
+                self.push(util::h160_to_u256(&address))
+            }
+            Halt::Revert => {
+                // The revert reason is the caller's only return data
+                state.revert_to(checkpoint);
+                self.gas += frame.gas;
+                self.return_data = frame.returns;
+                self.push(U256::zero())
+            }
+            _ => {
+                state.revert_to(checkpoint);
+                self.push(U256::zero())
+            }
+        }
+    }
+
+    /// 0xf1: call the code of an account with value
+    fn op_call<I: Inspector>(&mut self, state: &mut WorldState, inspector: &mut I) -> OpResult {
+        self.message_call(state, inspector, CallKind::Call)
+    }
+
+    /// 0xf2: run the code of another account in the context of this one
+    fn op_callcode<I: Inspector>(&mut self, state: &mut WorldState, inspector: &mut I) -> OpResult {
+        self.message_call(state, inspector, CallKind::CallCode)
+    }
+
+    /// 0xf4: run the code of another account with this frame's sender,
+    /// value and storage
+    fn op_delegatecall<I: Inspector>(
+        &mut self,
+        state: &mut WorldState,
+        inspector: &mut I,
+    ) -> OpResult {
+        self.message_call(state, inspector, CallKind::DelegateCall)
+    }
+
+    /// 0xfa: call the code of an account, forbidding any state change
+    fn op_staticcall<I: Inspector>(
+        &mut self,
+        state: &mut WorldState,
+        inspector: &mut I,
+    ) -> OpResult {
+        self.message_call(state, inspector, CallKind::StaticCall)
+    }
+
+    /// Run a nested message call in a new frame and push 1 on success, 0
+    /// otherwise. The frame's output is copied to the return region of
+    /// memory; its state changes are rolled back unless it succeeds.
+    fn message_call<I: Inspector>(
+        &mut self,
+        state: &mut WorldState,
+        inspector: &mut I,
+        kind: CallKind,
+    ) -> OpResult {
+        let transfers_value = kind == CallKind::Call || kind == CallKind::CallCode;
+        self.stack.check_pop_push(if transfers_value { 7 } else { 6 }, 1)?;
+
+        let requested_gas = self.pop()?;
+        let target = util::u256_to_h160(&self.pop()?);
+        let value = if transfers_value { self.pop()? } else { U256::zero() };
+        let args_offset = self.pop()?;
+        let args_length = self.pop()?;
+        let ret_offset = self.pop()?;
+        let ret_length = self.pop()?;
+
+        if self.is_static && kind == CallKind::Call && !value.is_zero() {
+            return Err(Halt::StaticViolation);
+        }
+
+        let mut cost = self.account_access_cost(state, target, self.schedule.call);
+        if !value.is_zero() {
+            cost += self.schedule.call_value;
+        }
+        if kind == CallKind::Call && self.is_new_account(state, target, value) {
+            cost += self.schedule.call_new_account;
+        }
+        self.consume_gas(cost)?;
+        let (args_offset, args_length) = self.expand_memory(args_offset, args_length)?;
+        let (ret_offset, ret_length) = self.expand_memory(ret_offset, ret_length)?;
+
+        // EIP-150: forward at most all but one 64th of the remaining gas;
+        // before it, the requested gas had to be available in full
+        let mut gas = if self.schedule.call_all_but_one_64th {
+            let available = self.gas - self.gas / 64;
+            if requested_gas > U256::from(available) {
+                available
+            } else {
+                requested_gas.low_u64() as usize
+            }
+        } else {
+            as_usize(requested_gas)?
+        };
+        self.consume_gas(gas)?;
+        if !value.is_zero() {
+            gas += self.schedule.call_stipend;
+        }
+
+        self.return_data.clear();
+        let address = self.env.code_supervisor;
+        if self.depth >= CALL_DEPTH_LIMIT || state.balance(address) < value {
+            self.gas += gas;
+            return self.push(U256::zero());
+        }
+
+        let (code_supervisor, sender, call_value) = match kind {
+            CallKind::Call | CallKind::StaticCall => (target, address, value),
+            CallKind::CallCode => (address, address, value),
+            CallKind::DelegateCall => (address, self.env.sender, self.env.call_value),
+        };
+        let jumps = match state.account(target) {
+            Some(account) => analysis::jump_table(account.code_hash, &account.code),
+            None => Rc::new(JumpTable::default()),
+        };
+        let env = Environment {
+            code: state.code(target).to_vec(),
+            input: self.memory.get(args_offset, args_length).to_vec(),
+            code_supervisor,
+            sender,
+            call_value,
+        };
+
+        let checkpoint = state.checkpoint();
+        if kind == CallKind::Call {
+            if value.is_zero() {
+                // Calling a missing account must not create it
+                state.touch_account(target);
+            } else {
+                state
+                    .transfer(address, target, value)
+                    .expect("balance checked before the call");
+            }
+        }
+
+        let is_static = self.is_static || kind == CallKind::StaticCall;
+        let mut frame = self.child_frame(env, jumps, gas, is_static);
+        inspector.call(self, &frame.call_inputs(kind, target));
+        let halt = match self.precompiles.get(target) {
+            Some(precompile) => frame.run_precompile(&*precompile),
+            None => frame.run(state, inspector),
+        };
+        inspector.call_end(
+            &frame.call_inputs(kind, target),
+            &FrameResult { halt, gas_left: frame.gas, output: &frame.returns },
+        );
+
+        let success = match halt {
+            Halt::Stop | Halt::Return => {
+                self.gas += frame.gas;
+                self.refund += frame.refund;
+                true
+            }
+            Halt::Revert => {
+                state.revert_to(checkpoint);
+                self.gas += frame.gas;
+                false
+            }
+            _ => {
+                state.revert_to(checkpoint);
+                frame.returns.clear();
+                false
+            }
+        };
+
+        self.return_data = frame.returns;
+        let copy_length = ret_length.min(self.return_data.len());
+        self.memory.set(ret_offset, &self.return_data[..copy_length]);
+        self.push(U256::from(success as u8))
+    }
+
+    /// 0xf3: halt and return memory[offset..offset + length] to the caller
+    fn op_return(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 0)?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+        let (offset, length) = self.expand_memory(offset, length)?;
+        self.returns = self.memory.get(offset, length).to_vec();
+        Err(Halt::Return)
+    }
+
+    /// 0xfd: halt, undoing the state changes of this frame, and return
+    /// memory[offset..offset + length] as the revert reason
+    fn op_revert(&mut self) -> OpResult {
+        self.stack.check_pop_push(2, 0)?;
+        let offset = self.pop()?;
+        let length = self.pop()?;
+        let (offset, length) = self.expand_memory(offset, length)?;
+        self.returns = self.memory.get(offset, length).to_vec();
+        Err(Halt::Revert)
+    }
+
+    /// 0xff: send the whole balance to a beneficiary and delete the
+    /// executing account when the transaction commits. Since Cancun only an
+    /// account created by the same transaction is deleted; any other one
+    /// just sends its balance (EIP-6780).
+    fn op_selfdestruct<I: Inspector>(
+        &mut self,
+        state: &mut WorldState,
+        inspector: &mut I,
+    ) -> OpResult {
+        self.stack.check_pop_push(1, 0)?;
+        if self.is_static {
+            return Err(Halt::StaticViolation);
+        }
+        let beneficiary = util::u256_to_h160(&self.pop()?);
+
+        let address = self.env.code_supervisor;
+        let balance = state.balance(address);
+        let mut cost = self.schedule.selfdestruct;
+        if self.schedule.access_lists && state.access_account(beneficiary) {
+            cost += self.schedule.cold_account_access;
+        }
+        if self.is_new_account(state, beneficiary, balance) {
+            cost += self.schedule.selfdestruct_new_account;
+        }
+        self.consume_gas(cost)?;
+
+        inspector.selfdestruct(address, beneficiary, balance);
+        state
+            .transfer(address, beneficiary, balance)
+            .expect("the whole balance is always available");
+        if !self.schedule.selfdestruct_only_created || state.created_in_transaction(address) {
+            // The balance is gone even if the account was its own beneficiary
+            let left = state.balance(address);
+            state
+                .sub_balance(address, left)
+                .expect("the whole balance is always available");
+            if state.selfdestruct(address) {
+                self.refund += self.schedule.selfdestruct_refund;
+            }
+        }
+        Err(Halt::Stop)
+    }
+
+    /// Returns true if sending `value` to `address` creates a new account
+    /// for gas purposes. Since EIP-161 only a non-zero value sent to an
+    /// empty account does; before it, any send to a missing account.
+    fn is_new_account(&self, state: &WorldState, address: H160, value: U256) -> bool {
+        if self.schedule.empty_account_is_dead {
+            !value.is_zero() && state.is_empty(address)
+        } else {
+            !state.exists(address)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tx::AccessListItem;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// Run `code` under `spec` in a contract whose slot 0 holds `original`
+    /// at the start of the transaction, with the slot in the access list.
+    /// Returns the outcome, the gas used before the refund and the refund
+    /// counter.
+    fn sstore(
+        spec: SpecId,
+        code: &str,
+        original: u64,
+        gas_limit: usize,
+    ) -> (ExecutionOutcome, usize, i64) {
+        let contract = H160::from_low_u64_be(0x1000);
+        let origin = H160::from_low_u64_be(0x01);
+        let mut state = WorldState::default();
+        state.set_storage(contract, U256::zero(), U256::from(original));
+        state.commit();
+
+        let mut env = Environment::new(contract, origin, U256::zero());
+        env.set_code(hex(code));
+        let access_list =
+            vec![AccessListItem { address: contract, storage_keys: vec![U256::zero()] }];
+        let tx = TxEnv { origin, gas_limit, access_list, ..TxEnv::default() };
+        let mut vm = AXISVM::new(env, tx, BlockEnv::default());
+        vm.set_spec(spec);
+        let outcome = vm.transaction_execute(&mut state);
+        (outcome, vm.gas_limit() - vm.gas(), vm.refund())
+    }
+
+    /// Code and original value of the test cases of EIP-2200 and EIP-3529,
+    /// in the order of the EIPs
+    const SSTORE_CASES: [(&str, u64); 17] = [
+        ("60006000556000600055", 0),
+        ("60006000556001600055", 0),
+        ("60016000556000600055", 0),
+        ("60016000556002600055", 0),
+        ("60016000556001600055", 0),
+        ("60006000556000600055", 1),
+        ("60006000556001600055", 1),
+        ("60006000556002600055", 1),
+        ("60026000556000600055", 1),
+        ("60026000556003600055", 1),
+        ("60026000556001600055", 1),
+        ("60026000556002600055", 1),
+        ("60016000556000600055", 1),
+        ("60016000556002600055", 1),
+        ("60016000556001600055", 1),
+        ("600160005560006000556001600055", 0),
+        ("600060005560016000556000600055", 1),
+    ];
+
+    /// Check the gas used and refund of each case against `expected`
+    fn check_sstore(spec: SpecId, expected: [(usize, i64); 17]) {
+        for (&(code, original), &(gas_used, refund)) in SSTORE_CASES.iter().zip(&expected) {
+            let (outcome, used, counter) = sstore(spec, code, original, 100_000);
+            assert!(matches!(outcome, ExecutionOutcome::Success { .. }), "{}", code);
+            assert_eq!((used, counter), (gas_used, refund), "{} from {}", code, original);
+        }
+    }
+
+    #[test]
+    fn sstore_istanbul() {
+        check_sstore(
+            SpecId::Istanbul,
+            [
+                (1612, 0),
+                (20812, 0),
+                (20812, 19200),
+                (20812, 0),
+                (20812, 0),
+                (5812, 15000),
+                (5812, 4200),
+                (5812, 0),
+                (5812, 15000),
+                (5812, 0),
+                (5812, 4200),
+                (5812, 0),
+                (5812, 15000),
+                (5812, 0),
+                (1612, 0),
+                (40818, 19200),
+                (10818, 19200),
+            ],
+        );
+    }
+
+    #[test]
+    fn sstore_london() {
+        check_sstore(
+            SpecId::London,
+            [
+                (212, 0),
+                (20112, 0),
+                (20112, 19900),
+                (20112, 0),
+                (20112, 0),
+                (3012, 4800),
+                (3012, 2800),
+                (3012, 0),
+                (3012, 4800),
+                (3012, 0),
+                (3012, 2800),
+                (3012, 0),
+                (3012, 4800),
+                (3012, 0),
+                (212, 0),
+                (40118, 19900),
+                (5918, 7600),
+            ],
+        );
+    }
+
+    /// EIP-2200: SSTORE fails when no more than the call stipend is left
+    #[test]
+    fn sstore_sentry() {
+        let (outcome, _, _) = sstore(SpecId::Istanbul, "6001600055", 1, 2306);
+        assert!(matches!(outcome, ExecutionOutcome::OutOfGas { .. }));
+        let (outcome, used, _) = sstore(SpecId::Istanbul, "6001600055", 1, 2307);
+        assert!(matches!(outcome, ExecutionOutcome::Success { .. }));
+        assert_eq!(used, 806);
+    }
+}
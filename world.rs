@@ -0,0 +1,532 @@
+//! Axis world state: accounts, balances, nonces, code and contract storage
+
+use std::collections::{HashMap, HashSet};
+
+extern crate rlp;
+use ethereum_types::{H160, H256, U256};
+use rlp::RlpStream;
+use super::hash;
+use super::journal::{Checkpoint, Journal, JournalEntry};
+use super::log::Log;
+use super::storage::{MemoryStorage, StorageBackend};
+use super::trie;
+
+/// Keccak-256 of empty code
+pub const KECCAK_EMPTY: H256 = H256([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+/// Root of an empty storage trie
+pub const EMPTY_STORAGE_ROOT: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Address of a contract created with CREATE: the last 20 bytes of
+/// `keccak(rlp([sender, nonce]))`.
+pub fn create_address(sender: H160, nonce: u64) -> H160 {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(&sender);
+    stream.append(&nonce);
+    H160::from_slice(&hash::keccak256(&stream.out())[12..])
+}
+
+/// Address of a contract created with CREATE2: the last 20 bytes of
+/// `keccak(0xff ++ sender ++ salt ++ keccak(init_code))`.
+pub fn create2_address(sender: H160, salt: H256, init_code_hash: H256) -> H160 {
+    let mut preimage = Vec::with_capacity(85);
+    preimage.push(0xff);
+    preimage.extend_from_slice(sender.as_bytes());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(init_code_hash.as_bytes());
+    H160::from_slice(&hash::keccak256(&preimage)[12..])
+}
+
+/// Errors of state transitions that the caller must handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// The sender cannot cover the value
+    InsufficientBalance,
+    /// The account nonce is already at its maximum
+    NonceOverflow,
+}
+
+/// An Axis account. Its storage slots live in the `StorageBackend`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Vec<u8>,
+    pub code_hash: H256,
+    /// Root of the storage trie as of the last commit, updated by
+    /// `WorldState::commit`
+    pub storage_root: H256,
+}
+
+impl Default for Account {
+    fn default() -> Account {
+        Account {
+            balance: U256::zero(),
+            nonce: 0,
+            code: Vec::new(),
+            code_hash: KECCAK_EMPTY,
+            storage_root: EMPTY_STORAGE_ROOT,
+        }
+    }
+}
+
+impl Account {
+    /// Returns true if the account has no code, zero nonce and zero
+    /// balance (EIP-161).
+    pub fn is_empty(&self) -> bool {
+        self.code_hash == KECCAK_EMPTY && self.nonce == 0 && self.balance.is_zero()
+    }
+}
+
+/// Accounts keyed by address, backed by a `StorageBackend`. Every change
+/// is journaled until `commit`, so that it can be rolled back to any
+/// earlier `checkpoint`.
+///
+/// The accounts are loaded from the backend when the state is created and
+/// kept in memory; `commit` writes the changed ones back together with the
+/// storage slots, so a persistent backend keeps balances, nonces and code
+/// across restarts as well. The storage root of every account whose
+/// storage changed is recomputed over all its slots on commit.
+///
+/// The state also tracks the accounts and storage slots accessed by the
+/// running transaction (EIP-2929). They are warm until the transaction
+/// commits, or until the frame that first accessed them reverts. Accounts
+/// touched by the transaction are tracked the same way, so that the empty
+/// ones can be removed (EIP-161).
+///
+/// Transient storage (EIP-1153) is journaled like contract storage and
+/// discarded when the transaction commits. Self-destructed accounts stay
+/// in place until then as well.
+pub struct WorldState {
+    accounts: HashMap<H160, Account>,
+    storage: Box<dyn StorageBackend>,
+    journal: Journal,
+    accessed_accounts: HashSet<H160>,
+    accessed_storage: HashSet<(H160, U256)>,
+    touched: HashSet<H160>,
+    transient: HashMap<(H160, U256), U256>,
+    created: HashSet<H160>,
+    selfdestructed: HashSet<H160>,
+}
+
+impl Default for WorldState {
+    fn default() -> WorldState {
+        WorldState::new(Box::new(MemoryStorage::default()))
+    }
+}
+
+impl WorldState {
+    /// State of the accounts committed to `storage`
+    pub fn new(storage: Box<dyn StorageBackend>) -> WorldState {
+        WorldState {
+            accounts: storage.accounts().into_iter().collect(),
+            storage,
+            journal: Journal::default(),
+            accessed_accounts: HashSet::new(),
+            accessed_storage: HashSet::new(),
+            touched: HashSet::new(),
+            transient: HashMap::new(),
+            created: HashSet::new(),
+            selfdestructed: HashSet::new(),
+        }
+    }
+
+    /// Account at `address`, if it exists
+    pub fn account(&self, address: H160) -> Option<&Account> {
+        self.accounts.get(&address)
+    }
+
+    pub fn exists(&self, address: H160) -> bool {
+        self.accounts.contains_key(&address)
+    }
+
+    /// Returns true if the account does not exist or is empty (EIP-161)
+    pub fn is_empty(&self, address: H160) -> bool {
+        self.account(address).map_or(true, Account::is_empty)
+    }
+
+    pub fn balance(&self, address: H160) -> U256 {
+        self.account(address).map_or_else(U256::zero, |account| account.balance)
+    }
+
+    pub fn nonce(&self, address: H160) -> u64 {
+        self.account(address).map_or(0, |account| account.nonce)
+    }
+
+    pub fn code(&self, address: H160) -> &[u8] {
+        self.account(address).map_or(&[][..], |account| &account.code[..])
+    }
+
+    /// Code hash as seen by EXTCODEHASH: zero for non-existent or empty
+    /// accounts.
+    pub fn code_hash(&self, address: H160) -> H256 {
+        match self.account(address) {
+            Some(account) if !account.is_empty() => account.code_hash,
+            _ => H256::zero(),
+        }
+    }
+
+    /// Account at `address`, created empty if it does not exist yet
+    fn touch(&mut self, address: H160) -> &mut Account {
+        if !self.accounts.contains_key(&address) {
+            self.journal.record(JournalEntry::AccountCreated { address });
+        }
+        self.mark_touched(address);
+        self.accounts.entry(address).or_insert_with(Account::default)
+    }
+
+    fn mark_touched(&mut self, address: H160) {
+        if self.touched.insert(address) {
+            self.journal.record(JournalEntry::AccountTouched { address });
+        }
+    }
+
+    /// Mark `address` as touched without changing it, e.g. by a call that
+    /// transfers no value. Missing accounts are not created.
+    pub fn touch_account(&mut self, address: H160) {
+        if self.exists(address) {
+            self.mark_touched(address);
+        }
+    }
+
+    /// Delete every account touched since the last commit that is empty
+    /// (EIP-161).
+    pub fn remove_touched_empty_accounts(&mut self) {
+        let empty: Vec<H160> = self
+            .touched
+            .iter()
+            .cloned()
+            .filter(|&address| self.account(address).map_or(false, Account::is_empty))
+            .collect();
+        for address in empty {
+            self.delete_account(address);
+        }
+    }
+
+    /// Create an empty account for a new contract at `address` unless one
+    /// already exists, and remember that the running transaction created
+    /// it.
+    pub fn create_account(&mut self, address: H160) {
+        self.touch(address);
+        if self.created.insert(address) {
+            self.journal.record(JournalEntry::ContractCreated { address });
+        }
+    }
+
+    /// Returns true if a contract was created at `address` since the last
+    /// commit.
+    pub fn created_in_transaction(&self, address: H160) -> bool {
+        self.created.contains(&address)
+    }
+
+    /// Schedule `address` for deletion when the transaction commits and
+    /// return true if it was not scheduled yet. Until then its code and
+    /// storage remain visible.
+    pub fn selfdestruct(&mut self, address: H160) -> bool {
+        let scheduled = self.selfdestructed.insert(address);
+        if scheduled {
+            self.journal.record(JournalEntry::SelfDestructed { address });
+        }
+        scheduled
+    }
+
+    /// Remove the account at `address`. Its storage is cleared when the
+    /// transaction commits.
+    pub fn delete_account(&mut self, address: H160) {
+        if let Some(account) = self.accounts.remove(&address) {
+            self.journal.record(JournalEntry::AccountDeleted { address, account });
+        }
+    }
+
+    /// Install `code` as the code of `address`, creating the account if
+    /// needed.
+    pub fn set_code(&mut self, address: H160, code: Vec<u8>) {
+        let code_hash = hash::keccak256(&code);
+        let account = self.touch(address);
+        let previous_code = ::std::mem::replace(&mut account.code, code);
+        let previous_hash = ::std::mem::replace(&mut account.code_hash, code_hash);
+        self.journal.record(JournalEntry::CodeChanged { address, previous_code, previous_hash });
+    }
+
+    /// Credit `value` to `address`, creating the account if needed.
+    pub fn add_balance(&mut self, address: H160, value: U256) {
+        let account = self.touch(address);
+        let previous = account.balance;
+        account.balance = previous.saturating_add(value);
+        self.journal.record(JournalEntry::BalanceChanged { address, previous });
+    }
+
+    /// Debit `value` from `address`.
+    pub fn sub_balance(&mut self, address: H160, value: U256) -> Result<(), StateError> {
+        if self.balance(address) < value {
+            return Err(StateError::InsufficientBalance);
+        }
+        if !value.is_zero() {
+            let account = self.touch(address);
+            let previous = account.balance;
+            account.balance = previous - value;
+            self.journal.record(JournalEntry::BalanceChanged { address, previous });
+        }
+        Ok(())
+    }
+
+    /// Move `value` from `from` to `to`, creating `to` if needed.
+    pub fn transfer(&mut self, from: H160, to: H160, value: U256) -> Result<(), StateError> {
+        self.sub_balance(from, value)?;
+        self.add_balance(to, value);
+        Ok(())
+    }
+
+    /// Increment the nonce of `address` and return the previous value.
+    pub fn increment_nonce(&mut self, address: H160) -> Result<u64, StateError> {
+        let account = self.touch(address);
+        let previous = account.nonce;
+        account.nonce = previous.checked_add(1).ok_or(StateError::NonceOverflow)?;
+        self.journal.record(JournalEntry::NonceChanged { address, previous });
+        Ok(previous)
+    }
+
+    /// Current value of a storage slot of `address`
+    pub fn storage(&self, address: H160, key: U256) -> U256 {
+        self.storage.get(address, key)
+    }
+
+    /// Value of a storage slot at the start of the transaction
+    pub fn original_storage(&self, address: H160, key: U256) -> U256 {
+        self.storage.committed(address, key)
+    }
+
+    pub fn set_storage(&mut self, address: H160, key: U256, value: U256) {
+        let previous = self.storage.get(address, key);
+        self.storage.set(address, key, value);
+        self.journal.record(JournalEntry::StorageChanged { address, key, previous });
+    }
+
+    /// Current value of a transient storage slot of `address`
+    pub fn transient_storage(&self, address: H160, key: U256) -> U256 {
+        self.transient.get(&(address, key)).cloned().unwrap_or_default()
+    }
+
+    pub fn set_transient_storage(&mut self, address: H160, key: U256, value: U256) {
+        let previous = self.transient_storage(address, key);
+        self.put_transient(address, key, value);
+        self.journal.record(JournalEntry::TransientStorageChanged { address, key, previous });
+    }
+
+    fn put_transient(&mut self, address: H160, key: U256, value: U256) {
+        if value.is_zero() {
+            self.transient.remove(&(address, key));
+        } else {
+            self.transient.insert((address, key), value);
+        }
+    }
+
+    /// Returns true if `address` was accessed earlier in the transaction.
+    pub fn is_warm_account(&self, address: H160) -> bool {
+        self.accessed_accounts.contains(&address)
+    }
+
+    /// Returns true if the slot was accessed earlier in the transaction.
+    pub fn is_warm_storage(&self, address: H160, key: U256) -> bool {
+        self.accessed_storage.contains(&(address, key))
+    }
+
+    /// Mark `address` as accessed and return true if it was cold.
+    pub fn access_account(&mut self, address: H160) -> bool {
+        let cold = self.accessed_accounts.insert(address);
+        if cold {
+            self.journal.record(JournalEntry::AccountWarmed { address });
+        }
+        cold
+    }
+
+    /// Mark a storage slot of `address` as accessed and return true if it
+    /// was cold.
+    pub fn access_storage(&mut self, address: H160, key: U256) -> bool {
+        let cold = self.accessed_storage.insert((address, key));
+        if cold {
+            self.journal.record(JournalEntry::StorageWarmed { address, key });
+        }
+        cold
+    }
+
+    /// Record a log emitted by a contract.
+    pub fn log(&mut self, log: Log) {
+        self.journal.log(log);
+    }
+
+    /// Logs emitted since the last commit
+    pub fn logs(&self) -> &[Log] {
+        self.journal.logs()
+    }
+
+    /// Mark the current state so that later changes can be undone.
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.journal.checkpoint()
+    }
+
+    /// Undo every change and log made after `checkpoint`.
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) {
+        for entry in self.journal.revert_to(checkpoint) {
+            match entry {
+                JournalEntry::StorageChanged { address, key, previous } => {
+                    self.storage.set(address, key, previous);
+                }
+                JournalEntry::TransientStorageChanged { address, key, previous } => {
+                    self.put_transient(address, key, previous);
+                }
+                JournalEntry::BalanceChanged { address, previous } => {
+                    if let Some(account) = self.accounts.get_mut(&address) {
+                        account.balance = previous;
+                    }
+                }
+                JournalEntry::NonceChanged { address, previous } => {
+                    if let Some(account) = self.accounts.get_mut(&address) {
+                        account.nonce = previous;
+                    }
+                }
+                JournalEntry::CodeChanged { address, previous_code, previous_hash } => {
+                    if let Some(account) = self.accounts.get_mut(&address) {
+                        account.code = previous_code;
+                        account.code_hash = previous_hash;
+                    }
+                }
+                JournalEntry::AccountCreated { address } => {
+                    self.accounts.remove(&address);
+                }
+                JournalEntry::AccountDeleted { address, account } => {
+                    self.accounts.insert(address, account);
+                }
+                JournalEntry::AccountWarmed { address } => {
+                    self.accessed_accounts.remove(&address);
+                }
+                JournalEntry::StorageWarmed { address, key } => {
+                    self.accessed_storage.remove(&(address, key));
+                }
+                JournalEntry::AccountTouched { address } => {
+                    self.touched.remove(&address);
+                }
+                JournalEntry::ContractCreated { address } => {
+                    self.created.remove(&address);
+                }
+                JournalEntry::SelfDestructed { address } => {
+                    self.selfdestructed.remove(&address);
+                }
+            }
+        }
+    }
+
+    /// Make every journaled change durable in the backend, deleting
+    /// self-destructed accounts and clearing the storage of deleted ones,
+    /// and return the logs emitted since the last commit. The next
+    /// transaction starts with every account and slot cold and with empty
+    /// transient storage.
+    pub fn commit(&mut self) -> Vec<Log> {
+        let selfdestructed: Vec<H160> = self.selfdestructed.drain().collect();
+        for address in selfdestructed {
+            self.delete_account(address);
+        }
+        for address in self.journal.deleted_accounts() {
+            self.storage.clear(address);
+        }
+        let storage_changed = self.journal.changed_storage();
+        for &address in &storage_changed {
+            let root = trie::storage_root(&self.storage.slots(address));
+            if let Some(account) = self.accounts.get_mut(&address) {
+                account.storage_root = root;
+            }
+        }
+
+        let mut changed = self.journal.changed_accounts();
+        changed.extend(storage_changed);
+        changed.sort();
+        changed.dedup();
+        for address in changed {
+            self.storage.set_account(address, self.accounts.get(&address).cloned());
+        }
+        self.storage.commit();
+        self.accessed_accounts.clear();
+        self.accessed_storage.clear();
+        self.touched.clear();
+        self.transient.clear();
+        self.created.clear();
+        self.journal.commit()
+    }
+
+    /// Drop every change made since the last commit.
+    pub fn revert(&mut self) {
+        self.revert_to(Checkpoint::default());
+        self.storage.revert();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn h160(s: &str) -> H160 {
+        H160::from_slice(&hex(s))
+    }
+
+    fn h256(s: &str) -> H256 {
+        H256::from_slice(&hex(s))
+    }
+
+    #[test]
+    fn create_addresses() {
+        let sender = h160("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(create_address(sender, 0), h160("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"));
+        assert_eq!(create_address(sender, 1), h160("343c43a37d37dff08ae8c4a11544c718abb4fcf8"));
+    }
+
+    /// The examples of EIP-1014
+    #[test]
+    fn create2_addresses() {
+        let cases = [
+            (
+                "0000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "00",
+                "4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38",
+            ),
+            (
+                "deadbeef00000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "00",
+                "b928f69bb1d91cd65274e3c79d8986362984fda3",
+            ),
+            (
+                "deadbeef00000000000000000000000000000000",
+                "000000000000000000000000feed000000000000000000000000000000000000",
+                "00",
+                "d04116cdd17bebe565eb2422f2497e06cc1c9833",
+            ),
+            (
+                "0000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "deadbeef",
+                "70f2b2914a2a4b783faefb75f459a580616fcb5e",
+            ),
+            (
+                "00000000000000000000000000000000deadbeef",
+                "00000000000000000000000000000000000000000000000000000000cafebabe",
+                "deadbeef",
+                "60f3f640a8508fc6a86d45df051962668e1e8ac7",
+            ),
+        ];
+        for &(sender, salt, init_code, address) in &cases {
+            let init_code_hash = hash::keccak256(&hex(init_code));
+            assert_eq!(create2_address(h160(sender), h256(salt), init_code_hash), h160(address));
+        }
+    }
+}